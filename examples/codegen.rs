@@ -0,0 +1,25 @@
+use rust_pact::codegen::{generate_bindings, generate_bindings_from_node};
+
+// Generate Rust bindings for a Pact module.
+//
+// From a node:       cargo run --example codegen -- coin testnet04 0 > src/coin.rs
+// From a saved JSON: cargo run --example codegen -- --file coin.json > src/coin.rs
+//   (the JSON is the describe-module result, or the whole /local response)
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.as_slice() {
+        [flag, path] if flag == "--file" => std::fs::read_to_string(path)
+            .map_err(|e| format!("cannot read {}: {}", path, e))
+            .and_then(|s| serde_json::from_str(&s).map_err(|e| format!("{} is not JSON: {}", path, e)))
+            .and_then(|described| generate_bindings(&described)),
+        [module, network_id, chain_id] => generate_bindings_from_node(module, network_id, chain_id),
+        _ => Err("usage: codegen <module> <network-id> <chain-id> | codegen --file <describe-module.json>".to_string()),
+    };
+    match result {
+        Ok(code) => print!("{}", code),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
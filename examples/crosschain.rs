@@ -27,13 +27,17 @@ fn main() {
     let sender_keypair = KeyPair {
         public_key: "10375651f1ca0110468152bb8f47b7b8a469e36dfab1c83adf60cab84b5726d3".into(),
         secret_key: "18d3a823139cf60cab0b738e7605bb9e4a2f3ff245c270fa55d197f9b3c4c004".into(),
-        clist: None
+        clist: None,
+        scheme: None,
+        addr: None
     };
 
     let receiver_keypair = KeyPair {
         public_key: "03df480e0b300c52901fdff265f0460913fea495f39972321698740536cc38e3".into(),
         secret_key: "".into(), // Not needed for completion, only public key
-        clist: None
+        clist: None,
+        scheme: None,
+        addr: None
     };
 
     let token_address = "coin";
@@ -77,7 +81,7 @@ fn main() {
 
     // Extract the request key (transaction hash) for polling
     if let Some(request_keys) = transfer_result.get("requestKeys").and_then(|rks| rks.as_array()) {
-        if let Some(request_key) = request_keys.get(0).and_then(|rk| rk.as_str()) {
+        if let Some(request_key) = request_keys.first().and_then(|rk| rk.as_str()) {
             println!("\nTransaction hash (request key): {}", request_key);
             
             // Step 2: Poll for transaction completion on source chain
@@ -204,7 +208,7 @@ fn has_transaction_result(poll_result: &serde_json::Value, request_key: &str) ->
     if let Some(result_map) = poll_result.as_object() {
         if let Some(tx_result) = result_map.get(request_key) {
             // Check if it's not null and has meaningful data
-            return !tx_result.is_null() && tx_result.as_object().map_or(false, |obj| !obj.is_empty());
+            return !tx_result.is_null() && tx_result.as_object().is_some_and(|obj| !obj.is_empty());
         }
     }
     false
@@ -253,7 +257,7 @@ fn poll_for_spv_proof(spv_cmd: &serde_json::Value, api_host: &str, max_attempts:
 fn has_spv_proof(spv_result: &serde_json::Value) -> bool {
     spv_result.get("proof")
         .and_then(|p| p.as_str())
-        .map_or(false, |proof| !proof.is_empty())
+        .is_some_and(|proof| !proof.is_empty())
 }
 
 fn extract_pact_id(poll_result: &serde_json::Value) -> Option<String> {
@@ -20,13 +20,17 @@ fn main() {
     let sender_keypair = KeyPair {
         public_key: "10375651f1ca0110468152bb8f47b7b8a469e36dfab1c83adf60cab84b5726d3".into(),
         secret_key: "18d3a823139cf60cab0b738e7605bb9e4a2f3ff245c270fa55d197f9b3c4c004".into(),
-        clist: None
+        clist: None,
+        scheme: None,
+        addr: None
     };
 
     let _receiver_keypair = KeyPair {
         public_key: "03df480e0b300c52901fdff265f0460913fea495f39972321698740536cc38e3".into(),
         secret_key: "".into(), // Not needed for completion, only public key
-        clist: None
+        clist: None,
+        scheme: None,
+        addr: None
     };
 
     let token_address = "coin";
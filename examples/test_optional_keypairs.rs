@@ -25,7 +25,9 @@ fn main() {
     let kp = KeyPair { 
         public_key: public_key.clone(), 
         secret_key: secret_key.clone(), 
-        clist: None 
+        clist: None,
+        scheme: None,
+        addr: None
     };
 
     let local_cmd_with_keys = json!({
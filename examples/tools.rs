@@ -1,4 +1,4 @@
-use rust_pact::{tools, gen_key_pair};
+use rust_pact::tools;
 use rust_pact::utils::KeyPair;
 
 // Run with: cargo run --example tools
@@ -8,7 +8,9 @@ fn main() {
     let key_pair = KeyPair {
         public_key: "10375651f1ca0110468152bb8f47b7b8a469e36dfab1c83adf60cab84b5726d3".into(),
         secret_key: "18d3a823139cf60cab0b738e7605bb9e4a2f3ff245c270fa55d197f9b3c4c004".into(),
-        clist: None
+        clist: None,
+        scheme: None,
+        addr: None
     };
 
     let token_address = "coin"; // same as Python call
@@ -0,0 +1,24 @@
+use rust_pact::vanity::{estimate_difficulty, generate_vanity_key_pair, VanityConfig};
+use std::sync::atomic::AtomicBool;
+
+// Run with: cargo run --release --example vanity
+fn main() {
+    let config = VanityConfig {
+        prefix: Some("cafe".into()),
+        ..Default::default()
+    };
+    let expected = match estimate_difficulty(&config) {
+        Ok(n) => n,
+        Err(e) => return println!("Invalid config: {}", e),
+    };
+    println!("Searching with {} threads, ~{} attempts expected", config.threads, expected);
+
+    let cancel = AtomicBool::new(false);
+    let mut report = |p: &rust_pact::VanityProgress| {
+        println!("  {} keys tried ({:.0} keys/s)", p.attempts, p.rate);
+    };
+    match generate_vanity_key_pair(&config, &cancel, Some(&mut report)) {
+        Ok(kp) => println!("Found account: k:{}", kp.public_key),
+        Err(e) => println!("Search failed: {}", e),
+    }
+}
@@ -0,0 +1,260 @@
+// pact! macro: Pact code templates checked at compile time, with escaped interpolation
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use rust_pact_syntax::expr::PactExpr;
+use rust_pact_syntax::parser::parse;
+use syn::parse::{Parse, ParseStream};
+use syn::{Expr, Ident, LitStr, Token};
+
+/// Build Pact code from a template, like `format!`.
+///
+/// The template is parsed as Pact at compile time, so unbalanced forms,
+/// bad strings or malformed numbers are compile errors. Placeholders
+/// (`{}`, `{0}`, `{name}`, with `name` an argument or a variable in scope)
+/// must stand for a whole expression and are filled through
+/// `rust_pact::ToPact`: strings are escaped, integers and decimals become
+/// number literals. Write `{{` and `}}` for Pact's own braces. Evaluates to
+/// a `String`.
+///
+/// ```text
+/// let code = pact!("(coin.transfer {sender} {receiver} {amount})");
+/// let code = pact!("(free.m.put {} {{\"v\": {v}}})", key, v = PactDecimal::parse("1.5")?);
+/// ```
+#[proc_macro]
+pub fn pact(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as Input);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+struct Input {
+    template: LitStr,
+    args: Vec<(Option<Ident>, Expr)>,
+}
+
+impl Parse for Input {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let template: LitStr = input.parse()?;
+        let mut args = Vec::new();
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            let name = if input.peek(Ident) && input.peek2(Token![=]) && !input.peek2(Token![==]) {
+                let name: Ident = input.parse()?;
+                input.parse::<Token![=]>()?;
+                Some(name)
+            } else {
+                None
+            };
+            args.push((name, input.parse()?));
+        }
+        Ok(Input { template, args })
+    }
+}
+
+enum Piece {
+    Text(String),
+    Arg(usize),
+}
+
+// Placeholders become these symbols for the syntax check
+const PLACEHOLDER: &str = "__pact_macro_arg_";
+
+fn expand(input: &Input) -> syn::Result<proc_macro2::TokenStream> {
+    let span = input.template.span();
+    let error = |message: String| syn::Error::new(span, message);
+    let template = input.template.value();
+
+    let positional = input.args.iter().take_while(|(name, _)| name.is_none()).count();
+    if input.args[positional..].iter().any(|(name, _)| name.is_none()) {
+        return Err(error("positional arguments must come before named ones".to_string()));
+    }
+    // argument expressions, then the variables placeholders capture by name
+    let mut values: Vec<proc_macro2::TokenStream> = input.args.iter().map(|(_, e)| quote!(#e)).collect();
+    let mut used = vec![false; input.args.len()];
+    let mut captured: Vec<(String, usize)> = Vec::new();
+    let mut next_positional = 0;
+
+    let mut pieces = Vec::new();
+    let mut text = String::new();
+    let mut check_src = String::new();
+    let mut placeholder_names = Vec::new();
+    let mut in_string = false;
+    let mut in_comment = false;
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                text.push('{');
+                check_src.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                text.push('}');
+                check_src.push('}');
+            }
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => name.push(c),
+                        None => return Err(error("unclosed placeholder; write {{ for a literal '{'".to_string())),
+                    }
+                }
+                if in_string || in_comment {
+                    return Err(error(format!("placeholder {{{}}} inside a Pact string or comment; interpolate the whole string instead", name)));
+                }
+                let name = name.trim();
+                let index = if name.is_empty() {
+                    next_positional += 1;
+                    if next_positional > positional {
+                        return Err(error(format!("more {{}} placeholders than the {} positional arguments", positional)));
+                    }
+                    next_positional - 1
+                } else if let Ok(i) = name.parse::<usize>() {
+                    if i >= positional {
+                        return Err(error(format!("no positional argument {}", i)));
+                    }
+                    i
+                } else if let Some(i) = input.args.iter().position(|(n, _)| n.as_ref().is_some_and(|n| n == name)) {
+                    i
+                } else if let Some((_, i)) = captured.iter().find(|(n, _)| n == name) {
+                    *i
+                } else {
+                    let ident: Ident = syn::parse_str(name)
+                        .map_err(|_| error(format!("invalid placeholder {{{}}}; write {{{{ and }}}} for Pact's braces", name)))?;
+                    values.push(quote!(#ident));
+                    used.push(true);
+                    captured.push((name.to_string(), values.len() - 1));
+                    values.len() - 1
+                };
+                used[index] = true;
+                pieces.push(Piece::Text(std::mem::take(&mut text)));
+                pieces.push(Piece::Arg(index));
+                let placeholder = format!("{}{}", PLACEHOLDER, pieces.len());
+                check_src.push_str(&placeholder);
+                placeholder_names.push(placeholder);
+                continue;
+            }
+            '}' => return Err(error("unmatched '}'; write }} for a literal '}'".to_string())),
+            _ => {
+                text.push(c);
+                check_src.push(c);
+            }
+        }
+        // track strings and comments so placeholders inside them are refused
+        match c {
+            '"' if !in_comment => in_string = !in_string,
+            '\\' if in_string => {
+                if let Some(escaped) = chars.next() {
+                    text.push(escaped);
+                    check_src.push(escaped);
+                }
+            }
+            ';' if !in_string => in_comment = true,
+            '\n' => in_comment = false,
+            _ => {}
+        }
+    }
+    pieces.push(Piece::Text(text));
+
+    if let Some(i) = used.iter().position(|u| !u) {
+        return Err(syn::Error::new_spanned(&input.args[i].1, "argument never used in the pact! template"));
+    }
+    check_syntax(&check_src, &placeholder_names).map_err(error)?;
+
+    let bindings: Vec<Ident> = (0..values.len()).map(|i| Ident::new(&format!("__pact_arg_{}", i), Span::mixed_site())).collect();
+    let parts = pieces.iter().map(|piece| match piece {
+        Piece::Text(t) if t.is_empty() => quote!(),
+        Piece::Text(t) => quote!(__pact_code.push_str(#t);),
+        Piece::Arg(i) => {
+            let binding = &bindings[*i];
+            quote!(__pact_code.push_str(&::rust_pact::expr::ToPact::to_pact_expr(#binding).to_string());)
+        }
+    });
+    Ok(quote! {
+        {
+            #(let #bindings = &(#values);)*
+            let mut __pact_code = ::std::string::String::new();
+            #(#parts)*
+            __pact_code
+        }
+    })
+}
+
+// Parse the template with placeholders as symbols and check each placeholder is a whole expression:
+// every placeholder name must come back as a symbol of its own, not glued to other characters
+fn check_syntax(src: &str, placeholders: &[String]) -> Result<(), String> {
+    let forms = parse(src).map_err(|e| format!("invalid Pact in pact! template, line {}: {}", e.span.line, e.message))?;
+    let mut found = Vec::new();
+    for form in &forms {
+        form.expr.walk(&mut |e| {
+            if let PactExpr::Symbol(s) = e {
+                if s.contains(PLACEHOLDER) {
+                    found.push(s.clone());
+                }
+            }
+        });
+    }
+    let mut expected = placeholders.to_vec();
+    expected.sort();
+    found.sort();
+    if found != expected {
+        return Err("a placeholder must stand for a whole expression, not part of a name or type".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand_str(input: &str) -> Result<String, String> {
+        let input: Input = syn::parse_str(input).map_err(|e| e.to_string())?;
+        expand(&input).map(|tokens| tokens.to_string()).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn valid_templates_expand() {
+        assert!(expand_str(r#""(coin.transfer {} {} {amount})", sender, receiver, amount = a"#).is_ok());
+        assert!(expand_str(r#""(f {0} {0} {{\"k\": {name}}})", x"#).is_ok());
+        assert!(expand_str(r#""(+ 100000000000000000000000 {})", n"#).is_ok());
+    }
+
+    #[test]
+    fn invalid_pact_is_rejected() {
+        assert!(expand_str(r#""(f 1""#).unwrap_err().contains("invalid Pact"));
+        assert!(expand_str(r#""(f \"a)""#).unwrap_err().contains("invalid Pact"));
+        assert!(expand_str(r#""(f 1.)""#).unwrap_err().contains("invalid Pact"));
+    }
+
+    #[test]
+    fn placeholders_must_be_whole_expressions() {
+        assert!(expand_str(r#""(f \"{}\")", x"#).unwrap_err().contains("inside a Pact string"));
+        assert!(expand_str(r#""(f {}) ; {}", x, y"#).unwrap_err().contains("inside a Pact string or comment"));
+        assert!(expand_str(r#""({f} 1)""#).is_ok());
+        assert!(expand_str(r#""(f x:{})", t"#).unwrap_err().contains("whole expression"));
+        assert!(expand_str(r#""(f {a}-x)", a = 1"#).unwrap_err().contains("whole expression"));
+        assert!(expand_str(r#""(f x-{a})", a = 1"#).unwrap_err().contains("whole expression"));
+        assert!(expand_str(r#""(f {a}.b)", a = 1"#).unwrap_err().contains("whole expression"));
+        assert!(expand_str(r#""(f {a}{b})", a = 1, b = 2"#).unwrap_err().contains("whole expression"));
+        assert!(expand_str(r#""(f __pact_macro_arg_2 {})", x"#).unwrap_err().contains("whole expression"));
+    }
+
+    #[test]
+    fn arguments_must_match_placeholders() {
+        assert!(expand_str(r#""(f {})", x, y"#).unwrap_err().contains("never used"));
+        assert!(expand_str(r#""(f {} {})", x"#).unwrap_err().contains("more {} placeholders"));
+        assert!(expand_str(r#""(f {1})", x"#).unwrap_err().contains("no positional argument 1"));
+        assert!(expand_str(r#""(f {a})", a = 1, 2"#).unwrap_err().contains("positional arguments must come before"));
+        assert!(expand_str(r#""(f {)""#).unwrap_err().contains("unclosed placeholder"));
+        assert!(expand_str(r#""(f })""#).unwrap_err().contains("unmatched '}'"));
+    }
+}
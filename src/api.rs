@@ -1,9 +1,10 @@
 // API module: command construction, signing, serialization
 // ...to be implemented...
-use crate::utils::{pull_check_hashs, pull_sig, mk_signer, KeyPair};
-use crate::crypto::{attach_sig};
+use crate::clock::{default_policy, TimePolicy};
+use crate::utils::{pull_check_hashs, pull_sig, KeyPair};
+use crate::types::{Command, Payload, PublicMeta, Signer, UnsignedCommand, Verifier};
 use serde_json::{json, Value};
-use chrono::Utc;
+use crate::nonce::next_nonce;
 
 pub fn filter_sig(sig: &Value) -> bool {
 	// Filter out signatures where sig is null or missing
@@ -15,54 +16,119 @@ pub fn filter_sig(sig: &Value) -> bool {
 pub fn mk_single_cmd(sigs: &[Value], cmd: &str) -> Value {
 	json!({
 		"hash": pull_check_hashs(sigs),
-		"sigs": sigs.iter().filter(|s| filter_sig(s)).map(|s| pull_sig(s)).collect::<Vec<_>>(),
+		"sigs": sigs.iter().filter(|s| filter_sig(s)).map(pull_sig).collect::<Vec<_>>(),
 		"cmd": cmd
 	})
 }
 
+/// Signed `{cmd, hash, sigs}` for `pact_code`, or `{"error": ..}` if `meta`
+/// is malformed or a signer's scheme or addr doesn't match its key.
+///
+/// `creationTime` and `ttl` missing from `meta` come from `clock::default_policy()`,
+/// and the ttl must be within its `max_ttl`.
 pub fn prepare_exec_cmd(pact_code: &str, env_data: Value, meta: Value, network_id: Option<String>, nonce: Option<String>, key_pairs: Option<Vec<KeyPair>>) -> Value {
-	let kp_vec = key_pairs.unwrap_or_default();
-	let signers: Vec<Value> = kp_vec.iter().map(|kp| mk_signer(kp)).collect();
-	let cmd_json = json!({
-		"networkId": network_id,
-		"payload": {
-			"exec": {
-				"data": env_data,
-				"code": pact_code
-			}
-		},
-		"signers": signers,
-		"meta": meta,
-		"nonce": nonce.unwrap_or_else(|| Utc::now().to_rfc3339())
-	});
-	let cmd = cmd_json.to_string();
-	let kp_json: Vec<Value> = kp_vec.iter().map(|kp| json!({"publicKey": kp.public_key, "secretKey": kp.secret_key})).collect();
-	let sigs = attach_sig(&cmd, &kp_json);
-	mk_single_cmd(&sigs, &cmd)
+	prepare_exec_cmd_with_verifiers(pact_code, env_data, meta, network_id, nonce, key_pairs, vec![])
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn prepare_cont_cmd(pact_id: &str, rollback: bool, step: u64, proof: Option<String>, env_data: Value, meta: Value, network_id: Option<String>, nonce: Option<String>, key_pairs: Option<Vec<KeyPair>>) -> Value {
+	prepare_cont_cmd_with_verifiers(pact_id, rollback, step, proof, env_data, meta, network_id, nonce, key_pairs, vec![])
+}
+
+/// `prepare_exec_cmd` with verifier plugins (e.g. Hyperlane message verification) attached to the command.
+pub fn prepare_exec_cmd_with_verifiers(pact_code: &str, env_data: Value, meta: Value, network_id: Option<String>, nonce: Option<String>, key_pairs: Option<Vec<KeyPair>>, verifiers: Vec<Verifier>) -> Value {
+	prepare_exec_cmd_with_policy(pact_code, env_data, meta, network_id, nonce, key_pairs, verifiers, &default_policy())
+}
+
+/// `prepare_cont_cmd` with verifier plugins attached to the command.
+#[allow(clippy::too_many_arguments)]
+pub fn prepare_cont_cmd_with_verifiers(pact_id: &str, rollback: bool, step: u64, proof: Option<String>, env_data: Value, meta: Value, network_id: Option<String>, nonce: Option<String>, key_pairs: Option<Vec<KeyPair>>, verifiers: Vec<Verifier>) -> Value {
+	prepare_cont_cmd_with_policy(pact_id, rollback, step, proof, env_data, meta, network_id, nonce, key_pairs, verifiers, &default_policy())
+}
+
+/// `prepare_exec_cmd_with_verifiers` with `policy` in place of the default time policy.
+#[allow(clippy::too_many_arguments)]
+pub fn prepare_exec_cmd_with_policy(pact_code: &str, env_data: Value, meta: Value, network_id: Option<String>, nonce: Option<String>, key_pairs: Option<Vec<KeyPair>>, verifiers: Vec<Verifier>, policy: &TimePolicy) -> Value {
+	let payload = Payload::exec(pact_code, env_data);
+	prepare_cmd(payload, meta, network_id, nonce, key_pairs, verifiers, policy).unwrap_or_else(|e| json!({"error": e}))
+}
+
+/// `prepare_cont_cmd_with_verifiers` with `policy` in place of the default time policy.
+#[allow(clippy::too_many_arguments)]
+pub fn prepare_cont_cmd_with_policy(pact_id: &str, rollback: bool, step: u64, proof: Option<String>, env_data: Value, meta: Value, network_id: Option<String>, nonce: Option<String>, key_pairs: Option<Vec<KeyPair>>, verifiers: Vec<Verifier>, policy: &TimePolicy) -> Value {
+	let payload = Payload::cont(pact_id, rollback, step, proof, env_data);
+	prepare_cmd(payload, meta, network_id, nonce, key_pairs, verifiers, policy).unwrap_or_else(|e| json!({"error": e}))
+}
+
+/// Prepare an exec command for external signing: no secret keys are needed,
+/// the result carries the hash and a `null` signature slot per signer.
+pub fn prepare_unsigned_exec_cmd(pact_code: &str, env_data: Value, meta: Value, network_id: Option<String>, nonce: Option<String>, signers: Vec<Signer>) -> Value {
+	let payload = Payload::exec(pact_code, env_data);
+	prepare_unsigned_cmd(payload, meta, network_id, nonce, signers, vec![]).map(|c| c.to_value()).unwrap_or_else(|e| json!({"error": e}))
+}
+
+/// Cont counterpart of `prepare_unsigned_exec_cmd`.
+#[allow(clippy::too_many_arguments)]
+pub fn prepare_unsigned_cont_cmd(pact_id: &str, rollback: bool, step: u64, proof: Option<String>, env_data: Value, meta: Value, network_id: Option<String>, nonce: Option<String>, signers: Vec<Signer>) -> Value {
+	let payload = Payload::cont(pact_id, rollback, step, proof, env_data);
+	prepare_unsigned_cmd(payload, meta, network_id, nonce, signers, vec![]).map(|c| c.to_value()).unwrap_or_else(|e| json!({"error": e}))
+}
+
+fn prepare_unsigned_cmd(payload: Payload, meta: Value, network_id: Option<String>, nonce: Option<String>, signers: Vec<Signer>, verifiers: Vec<Verifier>) -> Result<UnsignedCommand, String> {
+	let policy = default_policy();
+	check_signers(&signers)?;
+	let command = Command {
+		meta: meta_with_policy(&meta, &policy)?,
+		network_id,
+		nonce: nonce.unwrap_or_else(next_nonce),
+		payload,
+		signers,
+		verifiers,
+	};
+	Ok(command.to_unsigned())
+}
+
+// Signed `{cmd, hash, sigs}`; the public wrappers turn errors into `{"error": ..}`
+fn prepare_cmd(payload: Payload, meta: Value, network_id: Option<String>, nonce: Option<String>, key_pairs: Option<Vec<KeyPair>>, verifiers: Vec<Verifier>, policy: &TimePolicy) -> Result<Value, String> {
 	let kp_vec = key_pairs.unwrap_or_default();
-	let signers: Vec<Value> = kp_vec.iter().map(|kp| mk_signer(kp)).collect();
-	let cmd_json = json!({
-		"networkId": network_id,
-		"payload": {
-			"cont": {
-				"proof": proof,
-				"pactId": pact_id,
-				"rollback": rollback,
-				"step": step,
-				"data": env_data
-			}
-		},
-		"signers": signers,
-		"meta": meta,
-		"nonce": nonce.unwrap_or_else(|| Utc::now().to_rfc3339())
-	});
-	let cmd = cmd_json.to_string();
-	let kp_json: Vec<Value> = kp_vec.iter().map(|kp| json!({"publicKey": kp.public_key, "secretKey": kp.secret_key})).collect();
-	let sigs = attach_sig(&cmd, &kp_json);
-	mk_single_cmd(&sigs, &cmd)
+	let signers: Vec<Signer> = kp_vec.iter().map(Signer::from).collect();
+	check_signers(&signers)?;
+	let command = Command {
+		meta: meta_with_policy(&meta, policy)?,
+		network_id,
+		nonce: nonce.unwrap_or_else(next_nonce),
+		payload,
+		signers,
+		verifiers,
+	};
+	Ok(command.sign(&kp_vec)?.to_value())
+}
+
+// `meta` with creationTime and ttl defaulted from `policy`, whose ttl limits it must respect
+fn meta_with_policy(meta: &Value, policy: &TimePolicy) -> Result<PublicMeta, String> {
+	let mut meta = meta.clone();
+	if let Some(obj) = meta.as_object_mut() {
+		obj.entry("creationTime").or_insert_with(|| json!(policy.creation_time()));
+		obj.entry("ttl").or_insert_with(|| json!(policy.ttl));
+	}
+	let typed = PublicMeta::from_value(&meta)?;
+	policy.validate_ttl(typed.ttl)?;
+	Ok(typed)
+}
+
+// Signers that set scheme or addr must be self-consistent; plain signers are passed through as before
+fn check_signers(signers: &[Signer]) -> Result<(), String> {
+	for signer in signers.iter().filter(|s| s.scheme.is_some() || s.addr.is_some()) {
+		signer.validate().map_err(|e| format!("invalid signer: {}", e))?;
+	}
+	Ok(())
+}
+
+/// UNIX time after which a prepared `{cmd, hash, sigs}` command expires, None if `cmd` has no usable meta.
+pub fn cmd_expiry_time(prepared: &Value) -> Option<u64> {
+	let cmd: Value = serde_json::from_str(prepared.get("cmd")?.as_str()?).ok()?;
+	let meta = cmd.get("meta")?;
+	Some(meta.get("creationTime")?.as_u64()?.saturating_add(meta.get("ttl")?.as_u64()?))
 }
 
 pub fn mk_public_send(cmds: Vec<Value>) -> Value {
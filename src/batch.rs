@@ -0,0 +1,203 @@
+// Batch module: submit many commands across chains, grouped per chain and chunked per request
+use crate::api::mk_public_send;
+use crate::fetch::{make_prepare_cmd, try_post};
+use crate::tools::try_get_api_host;
+use crate::types::Command;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+/// Limits and routing for `send_batch`.
+///
+///  max_cmds_per_request  -> commands per /send request (default 100)
+///  max_request_bytes     -> serialized size of one /send body (default 1000000)
+///  api_host              -> maps (networkId, chainId) to a Pact endpoint (default `tools::try_get_api_host`)
+#[derive(Debug, Clone)]
+pub struct BatchConfig {
+    pub max_cmds_per_request: usize,
+    pub max_request_bytes: usize,
+    pub api_host: fn(&str, &str) -> Result<String, String>,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self { max_cmds_per_request: 100, max_request_bytes: 1_000_000, api_host: try_get_api_host }
+    }
+}
+
+/// Send commands that may target different chains (and networks).
+///
+/// Each entry is either a command object for `fetch::send` (signed here) or
+/// an already signed `{cmd, hash, sigs}`. Commands are grouped by the
+/// networkId and chainId in their `cmd`, split into chunks that respect
+/// `cfg`'s limits, and each chunk is posted to its chain's host. Chains are
+/// submitted in parallel, chunks of one chain in order.
+///
+/// Returns an object from request key to outcome:
+///  `{"status": "submitted", "chainId": .., "networkId": ..}` or
+///  `{"status": "failed", "chainId": .., "networkId": .., "error": ..}`.
+/// Commands that cannot even be routed are reported under their hash (or
+/// `"#<index>"` when they have none) with status "failed".
+pub fn send_batch(cmds: &[Value], cfg: &BatchConfig) -> Value {
+    let mut outcomes = Map::new();
+    let mut groups: BTreeMap<(String, String), Vec<(String, Value)>> = BTreeMap::new();
+
+    for (index, cmd) in cmds.iter().enumerate() {
+        let signed = if cmd.get("cmd").is_some() { cmd.clone() } else { make_prepare_cmd(cmd) };
+        let rk = signed.get("hash").and_then(|h| h.as_str()).map(|h| h.to_string()).unwrap_or_else(|| format!("#{}", index));
+        if let Some(e) = signed.get("error") {
+            outcomes.insert(rk, json!({"status": "failed", "error": e}));
+            continue;
+        }
+        match route(&signed) {
+            Ok(key) => groups.entry(key).or_default().push((rk, signed)),
+            Err(e) => { outcomes.insert(rk, json!({"status": "failed", "error": e})); }
+        }
+    }
+
+    let results: Vec<Map<String, Value>> = std::thread::scope(|scope| {
+        let handles: Vec<_> = groups.iter()
+            .map(|((network_id, chain_id), group)| scope.spawn(move || send_group(network_id, chain_id, group, cfg)))
+            .collect();
+        handles.into_iter().zip(&groups).map(|(h, ((network_id, chain_id), group))| {
+            // a panicking group must still report its commands; some chunks may have gone out already
+            h.join().unwrap_or_else(|_| {
+                let error = "sending this chain's commands panicked; some may have been submitted, check with /poll";
+                group.iter()
+                    .map(|(rk, _)| (rk.clone(), json!({"status": "failed", "chainId": chain_id, "networkId": network_id, "error": error})))
+                    .collect()
+            })
+        }).collect()
+    });
+    for result in results {
+        outcomes.extend(result);
+    }
+    Value::Object(outcomes)
+}
+
+/// Split signed commands into chunks of at most `max_cmds` commands and
+/// `max_bytes` serialized bytes (a single oversized command gets a chunk of its own).
+pub fn chunk_commands(cmds: &[Value], max_cmds: usize, max_bytes: usize) -> Vec<Vec<Value>> {
+    // {"cmds":[]} plus the commands and the commas between them
+    const ENVELOPE_BYTES: usize = 11;
+    let mut chunks: Vec<Vec<Value>> = Vec::new();
+    let mut current: Vec<Value> = Vec::new();
+    let mut current_bytes = ENVELOPE_BYTES;
+    for cmd in cmds {
+        let size = cmd.to_string().len();
+        if !current.is_empty() && (current.len() >= max_cmds.max(1) || current_bytes + 1 + size > max_bytes) {
+            chunks.push(std::mem::take(&mut current));
+            current_bytes = ENVELOPE_BYTES;
+        }
+        current_bytes += if current.is_empty() { size } else { size + 1 };
+        current.push(cmd.clone());
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+// (networkId, chainId) of a signed command
+fn route(signed: &Value) -> Result<(String, String), String> {
+    let cmd_str = signed.get("cmd").and_then(|c| c.as_str()).ok_or("no cmd string")?;
+    let command: Command = serde_json::from_str(cmd_str).map_err(|e| format!("malformed cmd: {}", e))?;
+    let network_id = command.network_id.filter(|n| !n.is_empty()).ok_or("command has no networkId")?;
+    if command.meta.chain_id.is_empty() {
+        return Err("command has no chainId".to_string());
+    }
+    Ok((network_id, command.meta.chain_id))
+}
+
+fn send_group(network_id: &str, chain_id: &str, group: &[(String, Value)], cfg: &BatchConfig) -> Map<String, Value> {
+    let mut outcomes = Map::new();
+    let outcome = |status: &str, error: Option<&str>| {
+        let mut o = json!({"status": status, "chainId": chain_id, "networkId": network_id});
+        if let Some(e) = error {
+            o["error"] = json!(e);
+        }
+        o
+    };
+    let host = match (cfg.api_host)(network_id, chain_id) {
+        Ok(host) => host,
+        Err(e) => {
+            for (rk, _) in group {
+                outcomes.insert(rk.clone(), outcome("failed", Some(&e)));
+            }
+            return outcomes;
+        }
+    };
+    let signed: Vec<Value> = group.iter().map(|(_, s)| s.clone()).collect();
+    for chunk in chunk_commands(&signed, cfg.max_cmds_per_request, cfg.max_request_bytes) {
+        let keys: Vec<String> = chunk.iter().filter_map(|c| c.get("hash").and_then(|h| h.as_str()).map(|h| h.to_string())).collect();
+        match try_post(&format!("{}/api/v1/send", host), &mk_public_send(chunk)) {
+            Ok(res) => {
+                let accepted: Vec<&str> = res.get("requestKeys").and_then(|v| v.as_array()).map(|a| a.iter().filter_map(|k| k.as_str()).collect()).unwrap_or_default();
+                for rk in keys {
+                    if accepted.contains(&rk.as_str()) {
+                        outcomes.insert(rk, outcome("submitted", None));
+                    } else {
+                        outcomes.insert(rk, outcome("failed", Some(&format!("not in send response: {}", res))));
+                    }
+                }
+            }
+            Err(e) => {
+                for rk in keys {
+                    outcomes.insert(rk, outcome("failed", Some(&e)));
+                }
+            }
+        }
+    }
+    outcomes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body_len(chunk: &[Value]) -> usize {
+        mk_public_send(chunk.to_vec()).to_string().len()
+    }
+
+    #[test]
+    fn chunks_respect_the_command_limit() {
+        let cmds: Vec<Value> = (0..7).map(|i| json!({"hash": i})).collect();
+        let sizes: Vec<usize> = chunk_commands(&cmds, 3, usize::MAX).iter().map(|c| c.len()).collect();
+        assert_eq!(sizes, [3, 3, 1]);
+        assert_eq!(chunk_commands(&cmds, 0, usize::MAX).len(), 7);
+        assert!(chunk_commands(&[], 3, 100).is_empty());
+    }
+
+    #[test]
+    fn chunks_fill_the_byte_limit_exactly() {
+        let cmds: Vec<Value> = (0..5).map(|i| json!(format!("command-{}", i))).collect();
+        let two = body_len(&cmds[..2]);
+        let chunks = chunk_commands(&cmds, 100, two);
+        assert_eq!(chunks.iter().map(|c| c.len()).collect::<Vec<_>>(), [2, 2, 1]);
+        assert!(chunks.iter().all(|c| body_len(c) <= two));
+        assert_eq!(chunk_commands(&cmds, 100, two - 1).len(), 5);
+        assert_eq!(chunk_commands(&cmds, 100, body_len(&cmds)).len(), 1);
+        assert_eq!(chunks.concat(), cmds);
+    }
+
+    #[test]
+    fn an_oversized_command_gets_a_chunk_of_its_own() {
+        let cmds = vec![json!("a"), json!("x".repeat(100)), json!("b")];
+        let chunks = chunk_commands(&cmds, 100, 50);
+        assert_eq!(chunks, [vec![json!("a")], vec![json!("x".repeat(100))], vec![json!("b")]]);
+    }
+
+    #[test]
+    fn commands_that_cannot_be_routed_or_hosted_fail() {
+        let command = |network: &str, chain: &str| json!({
+            "hash": format!("{}-{}", network, chain),
+            "cmd": json!({"networkId": network, "meta": {"chainId": chain, "creationTime": 1, "gasLimit": 1, "gasPrice": 1, "sender": "s", "ttl": 1}, "nonce": "n", "payload": {"exec": {"code": "1", "data": {}}}, "signers": []}).to_string(),
+            "sigs": []
+        });
+        let cmds = [json!({"cmd": 1}), command("", "0"), command("devnet", "0"), json!({"cmd": "{", "hash": "bad"})];
+        let out = send_batch(&cmds, &BatchConfig::default());
+        assert_eq!(out["#0"], json!({"status": "failed", "error": "no cmd string"}));
+        assert_eq!(out["-0"], json!({"status": "failed", "error": "command has no networkId"}));
+        assert_eq!(out["devnet-0"], json!({"status": "failed", "chainId": "0", "networkId": "devnet", "error": "Unsupported network_id: devnet"}));
+        assert!(out["bad"]["error"].as_str().unwrap().starts_with("malformed cmd"));
+    }
+}
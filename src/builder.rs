@@ -0,0 +1,240 @@
+// Builder module: fluent transaction construction in the style of @kadena/client
+use crate::clock::{default_policy, TimePolicy};
+use crate::env::EnvData;
+use crate::types::{Capability, Command, Payload, PublicMeta, Signer, SignerScheme, Verifier};
+use crate::nonce::{next_nonce, NonceStrategy};
+use serde_json::{json, Map, Value};
+
+pub const DEFAULT_GAS_LIMIT: u64 = 2500;
+pub const DEFAULT_GAS_PRICE: f64 = 0.00000001;
+
+/// Fluent builder for unsigned commands.
+///
+/// Defaults: nonce comes from the default nonce strategy (a unique RFC3339
+/// timestamp unless changed), creationTime and ttl
+/// follow the time policy (`clock::default_policy()` unless set), gasLimit
+/// is 2500 and gasPrice 0.00000001.
+#[derive(Debug, Clone, Default)]
+pub struct TransactionBuilder {
+    payload: Option<Payload>,
+    data: Map<String, Value>,
+    signers: Vec<Signer>,
+    verifiers: Vec<Verifier>,
+    chain_id: String,
+    sender: String,
+    gas_limit: Option<u64>,
+    gas_price: Option<f64>,
+    ttl: Option<u64>,
+    creation_time: Option<u64>,
+    network_id: Option<String>,
+    nonce: Option<String>,
+    nonce_strategy: Option<NonceStrategy>,
+    time_policy: Option<TimePolicy>,
+}
+
+impl TransactionBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Exec payload running `code`.
+    pub fn execution(mut self, code: &str) -> Self {
+        self.payload = Some(Payload::exec(code, Value::Null));
+        self
+    }
+
+    /// Cont payload resuming `pact_id` at `step`.
+    pub fn continuation(mut self, pact_id: &str, step: u64, rollback: bool, proof: Option<String>) -> Self {
+        self.payload = Some(Payload::cont(pact_id, rollback, step, proof, Value::Null));
+        self
+    }
+
+    /// Add an entry to the payload's env data.
+    pub fn add_data(mut self, key: &str, value: Value) -> Self {
+        self.data.insert(key.to_string(), value);
+        self
+    }
+
+    /// Add a keyset to env data, readable with `(read-keyset "name")`.
+    pub fn add_keyset(self, name: &str, pred: &str, keys: &[&str]) -> Self {
+        self.add_data(name, json!({"keys": keys, "pred": pred}))
+    }
+
+    /// Merge typed env data into the payload's env data.
+    pub fn add_env_data(mut self, env: EnvData) -> Self {
+        if let Value::Object(entries) = env.to_value() {
+            self.data.extend(entries);
+        }
+        self
+    }
+
+    /// Add a signer; an empty `caps` leaves the signature unscoped.
+    pub fn add_signer(mut self, pub_key: &str, caps: Vec<Capability>) -> Self {
+        let clist = if caps.is_empty() { None } else { Some(caps) };
+        self.signers.push(Signer::new(pub_key, clist));
+        self
+    }
+
+    /// Add a signer with an explicit scheme and optional address.
+    pub fn add_signer_with_scheme(mut self, pub_key: &str, scheme: SignerScheme, addr: Option<&str>, caps: Vec<Capability>) -> Self {
+        let clist = if caps.is_empty() { None } else { Some(caps) };
+        let mut signer = Signer::new(pub_key, clist).with_scheme(scheme);
+        signer.addr = addr.map(|a| a.to_string());
+        self.signers.push(signer);
+        self
+    }
+
+    /// Attach a verifier plugin proof, e.g. `hyperlane_v3_message`, granting `caps`.
+    pub fn add_verifier(mut self, name: &str, proof: Value, caps: Vec<Capability>) -> Self {
+        self.verifiers.push(Verifier::new(name, proof, caps));
+        self
+    }
+
+    pub fn set_meta(mut self, chain_id: &str, sender: &str) -> Self {
+        self.chain_id = chain_id.to_string();
+        self.sender = sender.to_string();
+        self
+    }
+
+    pub fn set_gas_limit(mut self, gas_limit: u64) -> Self {
+        self.gas_limit = Some(gas_limit);
+        self
+    }
+
+    pub fn set_gas_price(mut self, gas_price: f64) -> Self {
+        self.gas_price = Some(gas_price);
+        self
+    }
+
+    pub fn set_ttl(mut self, ttl: u64) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    pub fn set_creation_time(mut self, creation_time: u64) -> Self {
+        self.creation_time = Some(creation_time);
+        self
+    }
+
+    /// Time policy supplying the default creationTime and ttl and the max ttl.
+    pub fn set_time_policy(mut self, policy: TimePolicy) -> Self {
+        self.time_policy = Some(policy);
+        self
+    }
+
+    pub fn set_network_id(mut self, network_id: &str) -> Self {
+        self.network_id = Some(network_id.to_string());
+        self
+    }
+
+    pub fn set_nonce(mut self, nonce: &str) -> Self {
+        self.nonce = Some(nonce.to_string());
+        self
+    }
+
+    /// Strategy for the nonce when none is set with `set_nonce`.
+    pub fn set_nonce_strategy(mut self, strategy: NonceStrategy) -> Self {
+        self.nonce_strategy = Some(strategy);
+        self
+    }
+
+    /// Assemble the unsigned command. Sign it with `Command::sign`.
+    pub fn create_transaction(self) -> Result<Command, String> {
+        let data = Value::Object(self.data);
+        let payload = match self.payload {
+            Some(Payload::Exec(mut exec)) => { exec.data = data; Payload::Exec(exec) }
+            Some(Payload::Cont(mut cont)) => { cont.data = data; Payload::Cont(cont) }
+            None => return Err("transaction needs an execution or continuation payload".to_string()),
+        };
+        for signer in &self.signers {
+            signer.validate()?;
+        }
+        let policy = self.time_policy.unwrap_or_else(default_policy);
+        let ttl = self.ttl.unwrap_or(policy.ttl);
+        policy.validate_ttl(ttl)?;
+        let creation_time = self.creation_time.unwrap_or_else(|| policy.creation_time());
+        let gas_price = self.gas_price.unwrap_or(DEFAULT_GAS_PRICE);
+        if !gas_price.is_finite() {
+            return Err(format!("gas price must be a finite number, got {}", gas_price));
+        }
+        let meta = PublicMeta::new(
+            &self.sender,
+            &self.chain_id,
+            gas_price,
+            self.gas_limit.unwrap_or(DEFAULT_GAS_LIMIT),
+            creation_time,
+            ttl,
+        );
+        Ok(Command {
+            meta,
+            network_id: self.network_id,
+            nonce: self.nonce.unwrap_or_else(|| self.nonce_strategy.map(|s| s.next()).unwrap_or_else(next_nonce)),
+            payload,
+            signers: self.signers,
+            verifiers: self.verifiers,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FixedClock;
+    use std::sync::Arc;
+
+    const KEY: &str = "368820f80c324bbc7c2b0610688a7da43e39f91d118732671cd9c7500ff43cca";
+
+    fn policy() -> TimePolicy {
+        TimePolicy { clock: Arc::new(FixedClock(1_000_000)), skew_secs: 100, ttl: 600, max_ttl: 3600 }
+    }
+
+    #[test]
+    fn defaults_come_from_the_policy_and_constants() {
+        let cmd = TransactionBuilder::new()
+            .execution("(+ 1 2)")
+            .add_keyset("ks", "keys-all", &[KEY])
+            .add_data("n", json!(1))
+            .add_signer(KEY, vec![])
+            .set_meta("1", "k:a")
+            .set_nonce("n1")
+            .set_time_policy(policy())
+            .create_transaction()
+            .unwrap();
+        assert_eq!(cmd.meta, PublicMeta::new("k:a", "1", DEFAULT_GAS_PRICE, DEFAULT_GAS_LIMIT, 999_900, 600));
+        assert_eq!(cmd.payload, Payload::exec("(+ 1 2)", json!({"ks": {"keys": [KEY], "pred": "keys-all"}, "n": 1})));
+        assert_eq!(cmd.signers, vec![Signer::new(KEY, None)]);
+        assert_eq!((cmd.nonce.as_str(), cmd.network_id), ("n1", None));
+    }
+
+    #[test]
+    fn explicit_values_and_continuations() {
+        let cmd = TransactionBuilder::new()
+            .continuation("pid", 1, true, Some("proof".to_string()))
+            .add_data("k", json!("v"))
+            .add_signer(KEY, vec![Capability::gas()])
+            .set_gas_limit(10)
+            .set_gas_price(0.1)
+            .set_ttl(60)
+            .set_creation_time(5)
+            .set_network_id("testnet04")
+            .set_nonce_strategy(NonceStrategy::custom(|| "fixed".to_string()))
+            .set_time_policy(policy())
+            .create_transaction()
+            .unwrap();
+        assert_eq!(cmd.meta, PublicMeta::new("", "", 0.1, 10, 5, 60));
+        assert_eq!(cmd.payload, Payload::cont("pid", true, 1, Some("proof".to_string()), json!({"k": "v"})));
+        assert_eq!(cmd.signers[0].clist, Some(vec![Capability::gas()]));
+        assert_eq!((cmd.nonce.as_str(), cmd.network_id.as_deref()), ("fixed", Some("testnet04")));
+    }
+
+    #[test]
+    fn invalid_transactions_are_errors() {
+        let base = TransactionBuilder::new().execution("1").set_time_policy(policy());
+        assert!(TransactionBuilder::new().create_transaction().unwrap_err().contains("payload"));
+        assert!(base.clone().set_ttl(0).create_transaction().is_err());
+        assert!(base.clone().set_ttl(3601).create_transaction().unwrap_err().contains("exceeds"));
+        assert!(base.clone().set_gas_price(f64::NAN).create_transaction().unwrap_err().contains("finite"));
+        assert!(base.clone().add_signer("ab", vec![]).create_transaction().unwrap_err().contains("64 hex"));
+        assert!(base.add_signer_with_scheme(KEY, SignerScheme::Ed25519, Some("cd"), vec![]).create_transaction().unwrap_err().contains("does not match"));
+    }
+}
@@ -0,0 +1,132 @@
+// Clock module: time source and creationTime/TTL policy for prepared commands
+use std::fmt::Debug;
+use std::sync::{Arc, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Source of the current UNIX time in seconds.
+pub trait TimeSource: Debug + Send + Sync {
+    fn now(&self) -> u64;
+}
+
+/// Wall clock time.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl TimeSource for SystemClock {
+    fn now(&self) -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+    }
+}
+
+/// Always returns the same instant; useful for reproducible commands.
+#[derive(Debug, Clone, Copy)]
+pub struct FixedClock(pub u64);
+
+impl TimeSource for FixedClock {
+    fn now(&self) -> u64 {
+        self.0
+    }
+}
+
+/// How `creationTime` and `ttl` are chosen for new commands.
+///
+/// `creationTime` is the clock's time minus `skew_secs`, so that nodes whose
+/// clocks lag slightly behind ours still accept the command. `ttl` must not
+/// exceed `max_ttl`.
+///
+/// Env vars (all optional) for the default policy:
+///  PACT_TIME_SKEW_SECS -> seconds subtracted from now for creationTime (default 100)
+///  PACT_TTL            -> ttl in seconds (default 15000)
+///  PACT_MAX_TTL        -> largest accepted ttl in seconds (default 86400)
+#[derive(Debug, Clone)]
+pub struct TimePolicy {
+    pub clock: Arc<dyn TimeSource>,
+    pub skew_secs: u64,
+    pub ttl: u64,
+    pub max_ttl: u64,
+}
+
+impl Default for TimePolicy {
+    fn default() -> Self {
+        use std::env;
+        let skew_secs = env::var("PACT_TIME_SKEW_SECS").ok().and_then(|v| v.parse().ok()).unwrap_or(100);
+        let ttl = env::var("PACT_TTL").ok().and_then(|v| v.parse().ok()).unwrap_or(15000);
+        let max_ttl = env::var("PACT_MAX_TTL").ok().and_then(|v| v.parse().ok()).unwrap_or(86400);
+        Self { clock: Arc::new(SystemClock), skew_secs, ttl, max_ttl }
+    }
+}
+
+impl TimePolicy {
+    pub fn now(&self) -> u64 {
+        self.clock.now()
+    }
+
+    pub fn creation_time(&self) -> u64 {
+        self.now().saturating_sub(self.skew_secs)
+    }
+
+    pub fn validate_ttl(&self, ttl: u64) -> Result<(), String> {
+        if ttl == 0 {
+            return Err("ttl must be positive".to_string());
+        }
+        if ttl > self.max_ttl {
+            return Err(format!("ttl {} exceeds the maximum of {} seconds", ttl, self.max_ttl));
+        }
+        Ok(())
+    }
+
+    /// Seconds left before a command created at `creation_time` with `ttl` expires (0 once expired).
+    pub fn remaining(&self, creation_time: u64, ttl: u64) -> u64 {
+        creation_time.saturating_add(ttl).saturating_sub(self.now())
+    }
+
+    pub fn is_expired(&self, creation_time: u64, ttl: u64) -> bool {
+        self.remaining(creation_time, ttl) == 0
+    }
+}
+
+static DEFAULT_POLICY: RwLock<Option<TimePolicy>> = RwLock::new(None);
+
+/// Policy used where none is given explicitly, e.g. by the `tools` helpers.
+pub fn default_policy() -> TimePolicy {
+    DEFAULT_POLICY.read().unwrap_or_else(|e| e.into_inner()).clone().unwrap_or_default()
+}
+
+/// Replace the process-wide default policy.
+pub fn set_default_policy(policy: TimePolicy) {
+    *DEFAULT_POLICY.write().unwrap_or_else(|e| e.into_inner()) = Some(policy);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(now: u64) -> TimePolicy {
+        TimePolicy { clock: Arc::new(FixedClock(now)), skew_secs: 100, ttl: 600, max_ttl: 3600 }
+    }
+
+    #[test]
+    fn creation_time_is_skewed_back_but_not_below_zero() {
+        assert_eq!(policy(1000).creation_time(), 900);
+        assert_eq!(policy(50).creation_time(), 0);
+        assert_eq!(policy(1000).now(), 1000);
+    }
+
+    #[test]
+    fn ttl_must_be_positive_and_at_most_max_ttl() {
+        let p = policy(1000);
+        assert_eq!(p.validate_ttl(3600), Ok(()));
+        assert_eq!(p.validate_ttl(0), Err("ttl must be positive".to_string()));
+        assert_eq!(p.validate_ttl(3601), Err("ttl 3601 exceeds the maximum of 3600 seconds".to_string()));
+        assert!(crate::lang::mk_meta_with_policy("k:a", "0", 0.1, 1, &TimePolicy { ttl: 0, ..p }).is_err());
+    }
+
+    #[test]
+    fn remaining_time_saturates_at_expiry() {
+        let p = policy(1000);
+        assert_eq!(p.remaining(900, 600), 500);
+        assert_eq!(p.remaining(300, 600), 0);
+        assert_eq!(p.remaining(u64::MAX, u64::MAX), u64::MAX - 1000);
+        assert!(p.is_expired(400, 600) && !p.is_expired(401, 600));
+    }
+}
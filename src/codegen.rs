@@ -0,0 +1,276 @@
+// Codegen module: typed Rust bindings generated from a deployed module's interface
+use crate::expr::{DefKind, PactDef, PactExpr};
+use crate::parser::parse;
+use crate::tools;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fmt::Write;
+
+/// Generate Rust bindings for a module from the result of `(describe-module ..)`:
+/// either its `data` (`{"name": .., "code": ..}`, e.g. saved as JSON) or a
+/// whole `/local` response as returned by `tools::get_contract_code`.
+///
+/// The output is a Rust module named after the Pact module (`free.my-token`
+/// -> `free_my_token`) holding, per `defun` and `defpact`:
+///  `name_expr(args) -> PactExpr`          the call, for composing code
+///  `name(args) -> Result<TransactionBuilder, String>`
+///                                         a builder with that call as exec code,
+///                                         or the error from `PactExpr::render`
+/// and per `defcap` a constructor `caps::name(args) -> Capability` for clists.
+/// Pact `string`, `integer`, `decimal`, `bool` and `time` parameters become
+/// `&str`, `i64`, `impl Into<PactDecimal>`, `bool` and `DateTime<Utc>`; other
+/// types take `impl ToPact` (a `serde_json::Value` in capabilities).
+///
+/// ```text
+/// // build.rs
+/// let described: Value = serde_json::from_str(&std::fs::read_to_string("pact/coin.json")?)?;
+/// std::fs::write(format!("{}/coin.rs", std::env::var("OUT_DIR")?), generate_bindings(&described)?)?;
+/// // lib.rs: include!(concat!(env!("OUT_DIR"), "/coin.rs"));
+/// let amount = PactDecimal::parse("1.5")?;
+/// let tx = coin::transfer("k:ab..", "k:cd..", &amount)?.set_meta("0", "k:ab..").add_signer(pk, vec![coin::caps::transfer("k:ab..", "k:cd..", &amount)]);
+/// ```
+pub fn generate_bindings(described: &Value) -> Result<String, String> {
+    let data = described.pointer("/result/data").unwrap_or(described);
+    if let Some(status) = described.pointer("/result/status").and_then(|s| s.as_str()).filter(|s| *s != "success") {
+        return Err(format!("describe-module failed ({}): {}", status, described.pointer("/result/error").unwrap_or(&Value::Null)));
+    }
+    let name = data.get("name").and_then(|n| n.as_str()).ok_or("describe-module result has no name")?;
+    let code = data.get("code").and_then(|c| c.as_str()).ok_or("describe-module result has no code")?;
+    generate_bindings_from_source(name, code)
+}
+
+/// `generate_bindings` for a module fetched from a node with `tools::get_contract_code`.
+pub fn generate_bindings_from_node(module: &str, network_id: &str, chain_id: &str) -> Result<String, String> {
+    let described = tools::get_contract_code(module, network_id, chain_id);
+    if let Some(e) = described.get("error") {
+        return Err(format!("describe-module request failed: {}", e));
+    }
+    generate_bindings(&described)
+}
+
+/// Generate bindings for the module defined in Pact source `code`; `module`
+/// is its name as used in calls (`coin`, `free.my-token`).
+pub fn generate_bindings_from_source(module: &str, code: &str) -> Result<String, String> {
+    let forms = parse(code).map_err(|e| format!("cannot parse module code: {}", e))?;
+    let defs: Vec<&PactDef> = forms.iter()
+        .find_map(|f| match &f.expr {
+            PactExpr::App(head, args) if matches!(head.as_ref(), PactExpr::Symbol(h) if h == "module") => Some(args),
+            _ => None,
+        })
+        .ok_or_else(|| format!("{} is not a module", module))?
+        .iter()
+        .filter_map(|e| match e {
+            PactExpr::Def(def) => Some(def.as_ref()),
+            _ => None,
+        })
+        .collect();
+
+    let mut out = String::new();
+    let _ = writeln!(out, "// Generated by rust_pact::codegen from the interface of {}. Do not edit.", module);
+    let _ = writeln!(out, "pub mod {} {{", rust_ident(&module.replace('.', "_")));
+    let _ = writeln!(out, "    #![allow(dead_code, unused_imports, non_snake_case, clippy::too_many_arguments)]");
+    let _ = writeln!(out, "    use rust_pact::chrono::{{DateTime, Utc}};");
+    let _ = writeln!(out, "    use rust_pact::decimal::PactDecimal;");
+    let _ = writeln!(out, "    use rust_pact::env::time_json;");
+    let _ = writeln!(out, "    use rust_pact::expr::{{PactExpr, ToPact}};");
+    let _ = writeln!(out, "    use rust_pact::serde_json::{{json, Value}};");
+    let _ = writeln!(out, "    use rust_pact::types::Capability;");
+    let _ = writeln!(out, "    use rust_pact::TransactionBuilder;");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "    pub const MODULE: &str = {:?};", module);
+    let _ = writeln!(out);
+    let _ = writeln!(out, "    fn call(name: &str, args: Vec<PactExpr>) -> PactExpr {{");
+    let _ = writeln!(out, "        PactExpr::call(&format!(\"{{}}.{{}}\", MODULE, name), args).expect(\"names come from the module's code\")");
+    let _ = writeln!(out, "    }}");
+
+    let mut fn_names = Names::default();
+    for def in defs.iter().filter(|d| matches!(d.kind, DefKind::Defun | DefKind::Defpact)) {
+        let name = fn_names.take(&def.name);
+        let params = params(def);
+        let signature = params.iter().map(|p| format!("{}: {}", p.ident, p.ty.rust_type())).collect::<Vec<_>>().join(", ");
+        let idents = params.iter().map(|p| p.ident.clone()).collect::<Vec<_>>().join(", ");
+        let args = params.iter().map(|p| p.ty.to_expr(&p.ident)).collect::<Vec<_>>().join(", ");
+        let _ = writeln!(out);
+        write_doc(&mut out, "    ", def)?;
+        let _ = writeln!(out, "    pub fn {}_expr({}) -> PactExpr {{", name, signature);
+        let _ = writeln!(out, "        call({:?}, vec![{}])", def.name, args);
+        let _ = writeln!(out, "    }}");
+        let _ = writeln!(out);
+        write_doc(&mut out, "    ", def)?;
+        let _ = writeln!(out, "    pub fn {}({}) -> Result<TransactionBuilder, String> {{", name, signature);
+        let _ = writeln!(out, "        Ok(TransactionBuilder::new().execution(&{}_expr({}).render()?))", name, idents);
+        let _ = writeln!(out, "    }}");
+    }
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "    pub mod caps {{");
+    let _ = writeln!(out, "        use super::*;");
+    let mut cap_names = Names::default();
+    for def in defs.iter().filter(|d| d.kind == DefKind::Defcap) {
+        let name = cap_names.take(&def.name);
+        let params = params(def);
+        let signature = params.iter().map(|p| format!("{}: {}", p.ident, p.ty.cap_type())).collect::<Vec<_>>().join(", ");
+        let args = params.iter().map(|p| p.ty.to_json(&p.ident)).collect::<Vec<_>>().join(", ");
+        let _ = writeln!(out);
+        write_doc(&mut out, "        ", def)?;
+        let _ = writeln!(out, "        pub fn {}({}) -> Capability {{", name, signature);
+        let _ = writeln!(out, "            Capability::new(&format!(\"{{}}.{{}}\", MODULE, {:?}), vec![{}])", def.name, args);
+        let _ = writeln!(out, "        }}");
+    }
+    let _ = writeln!(out, "    }}");
+    let _ = writeln!(out, "}}");
+    Ok(out)
+}
+
+// Pact parameter types with a Rust counterpart; everything else is `Other`
+enum ParamType {
+    String,
+    Integer,
+    Decimal,
+    Bool,
+    Time,
+    Other,
+}
+
+impl ParamType {
+    fn from_pact(ty: Option<&str>) -> Self {
+        match ty {
+            Some("string") => ParamType::String,
+            Some("integer") => ParamType::Integer,
+            Some("decimal") => ParamType::Decimal,
+            Some("bool") => ParamType::Bool,
+            Some("time") => ParamType::Time,
+            _ => ParamType::Other,
+        }
+    }
+
+    fn rust_type(&self) -> &'static str {
+        match self {
+            ParamType::String => "&str",
+            ParamType::Integer => "i64",
+            ParamType::Decimal => "impl Into<PactDecimal>",
+            ParamType::Bool => "bool",
+            ParamType::Time => "DateTime<Utc>",
+            ParamType::Other => "impl ToPact",
+        }
+    }
+
+    fn cap_type(&self) -> &'static str {
+        match self {
+            ParamType::Other => "Value",
+            _ => self.rust_type(),
+        }
+    }
+
+    fn to_expr(&self, ident: &str) -> String {
+        match self {
+            ParamType::String => format!("PactExpr::string({})", ident),
+            ParamType::Integer => format!("PactExpr::integer({})", ident),
+            ParamType::Decimal => format!("PactExpr::decimal({})", ident),
+            ParamType::Bool => format!("PactExpr::bool({})", ident),
+            ParamType::Time => format!("PactExpr::time({})", ident),
+            ParamType::Other => format!("{}.to_pact_expr()", ident),
+        }
+    }
+
+    // Capability arguments use Pact's JSON encodings, as `env::EnvData` does
+    fn to_json(&self, ident: &str) -> String {
+        match self {
+            ParamType::String | ParamType::Bool => format!("json!({})", ident),
+            ParamType::Integer => format!("json!({{\"int\": {}}})", ident),
+            ParamType::Decimal => format!("{}.into().to_json()", ident),
+            ParamType::Time => format!("time_json({})", ident),
+            ParamType::Other => ident.to_string(),
+        }
+    }
+}
+
+struct Param {
+    ident: String,
+    ty: ParamType,
+}
+
+fn params(def: &PactDef) -> Vec<Param> {
+    let mut names = Names::default();
+    def.params.iter().flatten().map(|(name, ty)| Param { ident: names.take(name), ty: ParamType::from_pact(ty.as_deref()) }).collect()
+}
+
+// `@doc` or docstring of a definition, then its Pact signature
+fn write_doc(out: &mut String, indent: &str, def: &PactDef) -> Result<(), String> {
+    let doc = def.body.iter().find_map(|e| match e {
+        PactExpr::Meta(m, values) if m == "doc" => values.first().and_then(|v| match v {
+            PactExpr::String(s) => Some(s.clone()),
+            _ => None,
+        }),
+        _ => None,
+    }).or_else(|| match def.body.as_slice() {
+        [PactExpr::String(s), _, ..] => Some(s.clone()),
+        _ => None,
+    });
+    if let Some(doc) = doc {
+        for line in doc.lines() {
+            let _ = writeln!(out, "{}/// {}", indent, line.trim());
+        }
+        let _ = writeln!(out, "{}///", indent);
+    }
+    let header = PactExpr::Def(Box::new(PactDef { body: vec![], ..def.clone() }));
+    let _ = writeln!(out, "{}/// `{}`", indent, header.render()?);
+    Ok(())
+}
+
+// Rust identifiers for Pact names, unique within one scope
+#[derive(Default)]
+struct Names {
+    taken: HashSet<String>,
+}
+
+impl Names {
+    fn take(&mut self, pact_name: &str) -> String {
+        let base = rust_ident(pact_name);
+        let mut ident = base.clone();
+        let mut n = 2;
+        while self.taken.contains(&ident) || self.taken.contains(&format!("{}_expr", ident)) {
+            ident = format!("{}_{}", base, n);
+            n += 1;
+        }
+        self.taken.insert(ident.clone());
+        self.taken.insert(format!("{}_expr", ident));
+        ident
+    }
+}
+
+// transfer-create -> transfer_create, TRANSFER -> transfer, type -> type_
+fn rust_ident(pact_name: &str) -> String {
+    let mut ident: String = pact_name.chars().map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' }).collect();
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert(0, '_');
+    }
+    if RUST_KEYWORDS.contains(&ident.as_str()) || ident == "_" || ident == "call" || ident == "caps" {
+        ident.push('_');
+    }
+    ident
+}
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "break", "const", "continue", "crate", "else", "enum", "extern", "false", "fn", "for", "if", "impl", "in",
+    "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self", "static", "struct", "super",
+    "trait", "true", "type", "unsafe", "use", "where", "while", "async", "await", "dyn", "abstract", "become", "box",
+    "do", "final", "macro", "override", "priv", "typeof", "unsized", "virtual", "yield", "try", "gen",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_are_rust_identifiers_and_unique() {
+        let mut names = Names::default();
+        assert_eq!(["transfer-create", "TRANSFER", "transfer", "type", "call", "1x"].map(|n| names.take(n)), ["transfer_create", "transfer", "transfer_2", "type_", "call_", "_1x"]);
+    }
+
+    #[test]
+    fn modules_that_do_not_render_are_errors() {
+        assert!(generate_bindings_from_source("m", "(module m G (defun a' () 1))").is_err());
+        assert!(generate_bindings_from_source("m", "(defun f () 1)").is_err());
+    }
+}
@@ -0,0 +1,282 @@
+// Continuation module: drive arbitrary defpacts step by step, on one chain or across chains
+use crate::builder::DEFAULT_GAS_PRICE;
+use crate::fetch::LocalOptions;
+use crate::gas::{gas_from_local_response, GasEstimateConfig};
+use crate::tools::{poll_create_spv, try_get_api_host, CrossChainConfig};
+use crate::utils::KeyPair;
+use crate::{api, fetch, lang};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::thread::sleep;
+use std::time::{Duration, SystemTime};
+
+/// The `continuation` object of a transaction result that started or advanced a defpact.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PactContinuation {
+    pub pact_id: String,
+    /// Step that was just executed (0-based).
+    pub step: u64,
+    pub step_count: u64,
+    #[serde(default)]
+    pub step_has_rollback: bool,
+    /// Whether the step ran; `Some(false)` when it was skipped (e.g. a private
+    /// step for another entity), None when Pact doesn't track it.
+    #[serde(default)]
+    pub executed: Option<bool>,
+    #[serde(default, rename = "yield")]
+    pub yield_data: Option<PactYield>,
+    #[serde(default)]
+    pub continuation: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PactYield {
+    #[serde(default)]
+    pub data: Value,
+    #[serde(default)]
+    pub provenance: Option<Provenance>,
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+/// Present on a yield when the next step must run on another chain.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Provenance {
+    pub target_chain_id: String,
+    #[serde(default)]
+    pub module_hash: Option<String>,
+}
+
+impl PactContinuation {
+    /// Chain the next step must be submitted to, if the last step yielded cross-chain.
+    pub fn target_chain(&self) -> Option<&str> {
+        self.yield_data.as_ref().and_then(|y| y.provenance.as_ref()).map(|p| p.target_chain_id.as_str())
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.step + 1 >= self.step_count
+    }
+
+    /// False only when Pact reports that the step did not run.
+    pub fn step_executed(&self) -> bool {
+        self.executed != Some(false)
+    }
+}
+
+/// Result of submitting one continuation step.
+#[derive(Debug, Clone)]
+pub struct StepOutcome {
+    pub request_key: String,
+    pub chain_id: String,
+    pub step: u64,
+    pub rollback: bool,
+    /// The poll entry for `request_key` once mined.
+    pub result: Value,
+    /// Pact state after this step, None once the pact has finished.
+    pub continuation: Option<PactContinuation>,
+}
+
+/// Advances or rolls back defpacts given the request key of their last step.
+///
+/// Steps are signed by `key_pairs` (with whatever clist each carries) and gas
+/// is paid by `sender`, defaulting to the `k:` account of the first key pair.
+/// With no `gas_limit` each step's limit is estimated through preflight
+/// /local with `gas_estimate`. Polling and SPV timing follow `config`.
+#[derive(Debug, Clone)]
+pub struct DefpactDriver {
+    pub network_id: String,
+    pub key_pairs: Vec<KeyPair>,
+    pub sender: Option<String>,
+    pub gas_limit: Option<u64>,
+    pub gas_price: f64,
+    pub gas_estimate: GasEstimateConfig,
+    pub env_data: Value,
+    pub config: CrossChainConfig,
+}
+
+impl DefpactDriver {
+    pub fn new(network_id: &str, key_pairs: Vec<KeyPair>) -> Self {
+        Self {
+            network_id: network_id.to_string(),
+            key_pairs,
+            sender: None,
+            gas_limit: None,
+            gas_price: DEFAULT_GAS_PRICE,
+            gas_estimate: GasEstimateConfig::default(),
+            env_data: json!({}),
+            config: CrossChainConfig::default(),
+        }
+    }
+
+    /// Read the pact state left by the transaction `request_key` on `chain_id`.
+    pub fn pact_state(&self, request_key: &str, chain_id: &str) -> Result<PactContinuation, String> {
+        let api_host = try_get_api_host(&self.network_id, chain_id)?;
+        let res = fetch::poll(&json!({"requestKeys": [request_key]}), &api_host);
+        let entry = res.get(request_key).ok_or_else(|| format!("transaction {} not found on chain {}", request_key, chain_id))?;
+        continuation_of(entry)?.ok_or_else(|| format!("transaction {} did not start or continue a defpact", request_key))
+    }
+
+    /// Execute the next step of the pact, fetching an SPV proof when the last step yielded cross-chain.
+    pub fn advance(&self, request_key: &str, chain_id: &str) -> Result<StepOutcome, String> {
+        let state = self.pact_state(request_key, chain_id)?;
+        if state.is_complete() {
+            return Err(format!("pact {} has no step after {} (step count {})", state.pact_id, state.step, state.step_count));
+        }
+        if !state.step_executed() {
+            return Err(format!("step {} of pact {} did not execute, so the pact cannot continue", state.step, state.pact_id));
+        }
+        let (target_chain, proof) = match state.target_chain() {
+            Some(target) if target != chain_id => {
+                if self.config.verbose { println!("[defpact] fetching SPV proof {} -> {}", chain_id, target); }
+                let attempts = if self.config.attempts_spv > 0 { Some(self.config.attempts_spv) } else { None };
+                let proof = poll_create_spv(request_key, chain_id, target, &self.network_id, attempts, self.config.interval_spv_ms)?;
+                (target.to_string(), Some(proof))
+            }
+            _ => (chain_id.to_string(), None),
+        };
+        self.submit_step(&state, state.step + 1, false, proof, &target_chain)
+    }
+
+    /// Roll back the step that was just executed.
+    pub fn rollback(&self, request_key: &str, chain_id: &str) -> Result<StepOutcome, String> {
+        let state = self.pact_state(request_key, chain_id)?;
+        if !state.step_has_rollback {
+            return Err(format!("step {} of pact {} has no rollback", state.step, state.pact_id));
+        }
+        if !state.step_executed() {
+            return Err(format!("step {} of pact {} did not execute, so there is nothing to roll back", state.step, state.pact_id));
+        }
+        if state.target_chain().is_some_and(|t| t != chain_id) {
+            return Err(format!("step {} of pact {} yielded cross-chain and cannot be rolled back", state.step, state.pact_id));
+        }
+        self.submit_step(&state, state.step, true, None, chain_id)
+    }
+
+    /// Advance the pact until its last step has executed, returning every step's outcome.
+    pub fn run_to_completion(&self, request_key: &str, chain_id: &str) -> Result<Vec<StepOutcome>, String> {
+        let mut outcomes = Vec::new();
+        let mut rk = request_key.to_string();
+        let mut chain = chain_id.to_string();
+        loop {
+            let outcome = self.advance(&rk, &chain)?;
+            let finished = outcome.continuation.is_none();
+            rk = outcome.request_key.clone();
+            chain = outcome.chain_id.clone();
+            outcomes.push(outcome);
+            if finished {
+                return Ok(outcomes);
+            }
+        }
+    }
+
+    fn submit_step(&self, state: &PactContinuation, step: u64, rollback: bool, proof: Option<String>, chain_id: &str) -> Result<StepOutcome, String> {
+        let api_host = try_get_api_host(&self.network_id, chain_id)?;
+        let sender = match (&self.sender, self.key_pairs.first()) {
+            (Some(s), _) => s.clone(),
+            (None, Some(kp)) => format!("k:{}", kp.public_key),
+            (None, None) => return Err("no sender and no key pairs to pay gas".to_string()),
+        };
+        let prepare = |gas_limit: u64| {
+            let meta = lang::mk_meta_with_policy(&sender, chain_id, self.gas_price, gas_limit, &self.config.time_policy)?;
+            let cmd = api::prepare_cont_cmd(&state.pact_id, rollback, step, proof.clone(), self.env_data.clone(), meta, Some(self.network_id.clone()), None, Some(self.key_pairs.clone()));
+            match cmd.get("error") {
+                Some(e) => Err(format!("cannot prepare step {}: {}", step, e)),
+                None => Ok(cmd),
+            }
+        };
+        let gas_limit = match self.gas_limit {
+            Some(limit) => limit,
+            None => {
+                let probe = prepare(self.gas_estimate.block_gas_limit)?;
+                let res = fetch::local_signed(&probe, &api_host, Some(LocalOptions { preflight: Some(true), signature_verification: None }));
+                self.gas_estimate.limit_for(gas_from_local_response(&res)?)?
+            }
+        };
+        let cmd = prepare(gas_limit)?;
+        if self.config.verbose { println!("[defpact] submitting step {} (rollback: {}) of {} on chain {}", step, rollback, state.pact_id, chain_id); }
+
+        let send_res = fetch::send(&api::mk_public_send(vec![cmd]), &api_host, false);
+        let rk = send_res.get("requestKeys").and_then(|v| v.as_array()).and_then(|arr| arr.first()).and_then(|v| v.as_str())
+            .ok_or_else(|| format!("send failed: {}", send_res))?
+            .to_string();
+
+        let result = wait_for_result(&rk, &api_host, &self.config)?;
+        if result.get("result").and_then(|r| r.get("status")).and_then(|s| s.as_str()) != Some("success") {
+            let msg = result.get("result").and_then(|r| r.get("error")).and_then(|e| e.get("message")).and_then(|m| m.as_str()).unwrap_or("unknown error");
+            return Err(format!("step {} of pact {} failed: {}", step, state.pact_id, msg));
+        }
+        let continuation = continuation_of(&result)?.filter(|c| !rollback && !c.is_complete());
+        Ok(StepOutcome { request_key: rk, chain_id: chain_id.to_string(), step, rollback, result, continuation })
+    }
+}
+
+fn continuation_of(entry: &Value) -> Result<Option<PactContinuation>, String> {
+    match entry.get("continuation") {
+        Some(c) if !c.is_null() => serde_json::from_value(c.clone()).map(Some).map_err(|e| format!("malformed continuation: {}", e)),
+        _ => Ok(None),
+    }
+}
+
+fn wait_for_result(request_key: &str, api_host: &str, cfg: &CrossChainConfig) -> Result<Value, String> {
+    let poll_req = json!({"requestKeys": [request_key]});
+    let start_time = SystemTime::now();
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let res = fetch::poll(&poll_req, api_host);
+        if let Some(entry) = res.get(request_key) {
+            return Ok(entry.clone());
+        }
+        if cfg.attempts_final > 0 && attempt >= cfg.attempts_final {
+            return Err(format!("max attempts ({}) reached waiting for {}", cfg.attempts_final, request_key));
+        }
+        if cfg.max_total_time_ms > 0 && start_time.elapsed().map(|e| e.as_millis() as u64 >= cfg.max_total_time_ms).unwrap_or(false) {
+            return Err(format!("timeout after {}ms waiting for {}", cfg.max_total_time_ms, request_key));
+        }
+        sleep(Duration::from_millis(cfg.interval_final_ms));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(continuation: Value) -> Value {
+        json!({"reqKey": "rk", "result": {"status": "success"}, "continuation": continuation})
+    }
+
+    #[test]
+    fn continuations_are_read_from_poll_entries() {
+        let c = continuation_of(&entry(json!({
+            "pactId": "pid", "step": 0, "stepCount": 2, "stepHasRollback": false, "executed": null,
+            "yield": {"data": {"amount": 1}, "provenance": {"targetChainId": "2", "moduleHash": "h"}, "source": "1"},
+            "continuation": {"def": "coin.transfer-crosschain", "args": []}
+        }))).unwrap().unwrap();
+        assert_eq!((c.pact_id.as_str(), c.step, c.step_count), ("pid", 0, 2));
+        assert_eq!(c.target_chain(), Some("2"));
+        assert!(!c.is_complete() && c.step_executed());
+
+        assert_eq!(continuation_of(&entry(Value::Null)), Ok(None));
+        assert_eq!(continuation_of(&json!({"result": {}})), Ok(None));
+        assert!(continuation_of(&entry(json!({"pactId": "pid"}))).unwrap_err().starts_with("malformed continuation"));
+    }
+
+    #[test]
+    fn step_state() {
+        let mut c: PactContinuation = serde_json::from_value(json!({"pactId": "p", "step": 1, "stepCount": 2, "executed": false})).unwrap();
+        assert!(c.is_complete() && !c.step_executed() && c.target_chain().is_none());
+        assert!(!c.step_has_rollback && c.yield_data.is_none());
+        c.executed = None;
+        assert!(c.step_executed());
+    }
+
+    #[test]
+    fn unknown_networks_are_errors() {
+        let driver = DefpactDriver::new("devnet", vec![]);
+        assert_eq!(driver.advance("rk", "0").unwrap_err(), "Unsupported network_id: devnet");
+        assert_eq!(driver.rollback("rk", "0").unwrap_err(), "Unsupported network_id: devnet");
+    }
+}
@@ -0,0 +1,169 @@
+// Env module: typed builder for a command's env data and the code that reads it
+use crate::decimal::PactDecimal;
+use crate::expr::PactExpr;
+use chrono::{DateTime, Timelike, Utc};
+use serde_json::{json, Map, Value};
+
+/// Env data (`envData` / payload `data`) built from Pact-typed values.
+///
+/// Values use Pact's JSON encodings: keysets `{"keys": [..], "pred": ..}`,
+/// decimals `{"decimal": "1.5"}`, integers `{"int": 3}` and times
+/// `{"time": "2024-01-01T00:00:00Z"}`. Since the builder knows what each
+/// entry is, it can also produce the expression that reads it back, so the
+/// code and the data cannot disagree on a name:
+///
+/// ```text
+/// let env = EnvData::new().add_keyset("ks", "keys-all", &[pk]);
+/// let code = format!("(coin.create-account \"k:{}\" {})", pk, env.read("ks")?);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EnvData {
+    entries: Map<String, Value>,
+    keysets: Vec<String>,
+}
+
+impl EnvData {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keyset of `keys` under predicate `pred` (`keys-all`, `keys-any`, `keys-2` or a custom one).
+    pub fn add_keyset(mut self, name: &str, pred: &str, keys: &[&str]) -> Self {
+        self.entries.insert(name.to_string(), json!({"keys": keys, "pred": pred}));
+        if !self.keysets.iter().any(|k| k == name) {
+            self.keysets.push(name.to_string());
+        }
+        self
+    }
+
+    pub fn add_decimal(self, name: &str, value: impl Into<PactDecimal>) -> Self {
+        self.add_value(name, value.into().to_json())
+    }
+
+    pub fn add_integer(self, name: &str, value: i64) -> Self {
+        self.add_value(name, json!({"int": value}))
+    }
+
+    /// Time encoded with `time_json`.
+    pub fn add_time(self, name: &str, value: DateTime<Utc>) -> Self {
+        self.add_value(name, time_json(value))
+    }
+
+    pub fn add_string(self, name: &str, value: &str) -> Self {
+        self.add_value(name, json!(value))
+    }
+
+    pub fn add_bool(self, name: &str, value: bool) -> Self {
+        self.add_value(name, json!(value))
+    }
+
+    /// Nested object, read in code with `(at "field" (read-msg "name"))`.
+    pub fn add_object(self, name: &str, value: EnvData) -> Self {
+        self.add_value(name, value.to_value())
+    }
+
+    /// Any JSON value, passed through as is.
+    pub fn add_value(mut self, name: &str, value: Value) -> Self {
+        self.keysets.retain(|k| k != name);
+        self.entries.insert(name.to_string(), value);
+        self
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.contains_key(name)
+    }
+
+    /// `(read-keyset "name")`; fails if `name` is not a keyset of this env data.
+    pub fn read_keyset(&self, name: &str) -> Result<String, String> {
+        if !self.keysets.iter().any(|k| k == name) {
+            return Err(format!("env data has no keyset {:?}", name));
+        }
+        Ok(PactExpr::read_keyset(name).to_string())
+    }
+
+    /// `(read-msg "name")`; fails if `name` is not in this env data.
+    pub fn read_msg(&self, name: &str) -> Result<String, String> {
+        if !self.contains(name) {
+            return Err(format!("env data has no entry {:?}", name));
+        }
+        Ok(PactExpr::read_msg(name).to_string())
+    }
+
+    /// `read_keyset` for keysets, `read_msg` for everything else.
+    pub fn read(&self, name: &str) -> Result<String, String> {
+        self.read_expr(name).map(|e| e.to_string())
+    }
+
+    /// `read` as an expression, for building code with `PactExpr`.
+    pub fn read_expr(&self, name: &str) -> Result<PactExpr, String> {
+        if self.keysets.iter().any(|k| k == name) {
+            Ok(PactExpr::read_keyset(name))
+        } else if self.contains(name) {
+            Ok(PactExpr::read_msg(name))
+        } else {
+            Err(format!("env data has no entry {:?}", name))
+        }
+    }
+
+    pub fn to_value(&self) -> Value {
+        Value::Object(self.entries.clone())
+    }
+}
+
+impl From<EnvData> for Value {
+    fn from(env: EnvData) -> Value {
+        Value::Object(env.entries)
+    }
+}
+
+/// Time with second precision as `{"time": ..}`, or microsecond precision as `{"timep": ..}`.
+pub fn time_json(value: DateTime<Utc>) -> Value {
+    if value.nanosecond() == 0 {
+        json!({"time": value.format("%Y-%m-%dT%H:%M:%SZ").to_string()})
+    } else {
+        json!({"timep": value.format("%Y-%m-%dT%H:%M:%S%.6fZ").to_string()})
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_use_pact_json_encodings() {
+        let at: DateTime<Utc> = "2024-01-02T03:04:05Z".parse().unwrap();
+        let env = EnvData::new()
+            .add_keyset("ks", "keys-2", &["a", "b"])
+            .add_decimal("amount", PactDecimal::from(2))
+            .add_integer("count", -3)
+            .add_time("at", at)
+            .add_time("precise", at + chrono::Duration::microseconds(1500))
+            .add_string("name", "x\"y")
+            .add_bool("flag", true)
+            .add_object("nested", EnvData::new().add_integer("i", 1));
+        assert_eq!(Value::from(env), json!({
+            "ks": {"keys": ["a", "b"], "pred": "keys-2"},
+            "amount": {"decimal": "2.0"},
+            "count": {"int": -3},
+            "at": {"time": "2024-01-02T03:04:05Z"},
+            "precise": {"timep": "2024-01-02T03:04:05.001500Z"},
+            "name": "x\"y",
+            "flag": true,
+            "nested": {"i": {"int": 1}},
+        }));
+    }
+
+    #[test]
+    fn reads_match_what_was_added() {
+        let env = EnvData::new().add_keyset("ks", "keys-all", &["a"]).add_string("msg", "m");
+        assert_eq!(env.read("ks"), Ok("(read-keyset \"ks\")".to_string()));
+        assert_eq!(env.read("msg"), Ok("(read-msg \"msg\")".to_string()));
+        assert_eq!(env.read_msg("ks"), Ok("(read-msg \"ks\")".to_string()));
+        assert_eq!(env.read_keyset("msg"), Err("env data has no keyset \"msg\"".to_string()));
+        assert!(env.read("other").is_err() && env.read_msg("other").is_err());
+
+        let replaced = env.add_string("ks", "no longer a keyset");
+        assert_eq!(replaced.read_expr("ks"), Ok(PactExpr::read_msg("ks")));
+        assert!(replaced.read_keyset("ks").is_err());
+    }
+}
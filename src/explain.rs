@@ -0,0 +1,249 @@
+// Explain module: human-readable rendering of a command for signing review
+use crate::clock::default_policy;
+use crate::decimal::PactDecimal;
+use crate::types::{Capability, Command, Payload, Signer};
+use crate::validate::{validate_command, ValidationConfig};
+use chrono::DateTime;
+use serde_json::Value;
+use std::fmt::Write;
+
+/// Explain a prepared command: `{"cmd": ..., ...}` (signed or not) or a bare `cmd` string.
+pub fn explain(prepared: &Value) -> Result<String, String> {
+    let cmd_str = match prepared {
+        Value::String(s) => s.as_str(),
+        _ => prepared.get("cmd").and_then(|c| c.as_str()).ok_or("no cmd string to explain")?,
+    };
+    let command: Command = serde_json::from_str(cmd_str).map_err(|e| format!("malformed cmd: {}", e))?;
+    Ok(explain_command(&command))
+}
+
+/// Render what `command` does: where it runs, what it may cost, when it
+/// expires, its code and data, what each signer authorizes, and warnings.
+pub fn explain_command(command: &Command) -> String {
+    let meta = &command.meta;
+    let mut out = String::new();
+
+    let _ = writeln!(out, "Network:  {}", command.network_id.as_deref().unwrap_or("(missing)"));
+    let _ = writeln!(out, "Chain:    {}", meta.chain_id);
+    let _ = writeln!(out, "Sender:   {}", meta.sender);
+    let gas_price = serde_json::from_value::<PactDecimal>(Value::Number(meta.gas_price.clone()));
+    let (max_cost, price) = match gas_price {
+        Ok(price) => ((PactDecimal::from(meta.gas_limit) * price.clone()).to_pact(), price.to_pact()),
+        Err(_) => ("?".to_string(), meta.gas_price.to_string()),
+    };
+    let _ = writeln!(out, "Gas:      up to {} KDA ({} gas at {} KDA)", max_cost, meta.gas_limit, price);
+    let _ = writeln!(out, "Created:  {}", timestamp(meta.creation_time));
+    let _ = writeln!(out, "Expires:  {} (ttl {}s)", timestamp(meta.expires_at()), meta.ttl);
+    let _ = writeln!(out, "Nonce:    {}", command.nonce);
+
+    let data = match &command.payload {
+        Payload::Exec(exec) => {
+            let _ = writeln!(out, "Code:");
+            for line in exec.code.lines() {
+                let _ = writeln!(out, "  {}", line);
+            }
+            &exec.data
+        }
+        Payload::Cont(cont) => {
+            let action = if cont.rollback { "Roll back" } else { "Continue" };
+            let _ = writeln!(out, "{} step {} of pact {}{}", action, cont.step, cont.pact_id, if cont.proof.is_some() { " (with SPV proof)" } else { "" });
+            &cont.data
+        }
+    };
+    explain_data(&mut out, data);
+
+    let _ = writeln!(out, "Signers:");
+    if command.signers.is_empty() {
+        let _ = writeln!(out, "  (none)");
+    }
+    for signer in &command.signers {
+        explain_signer(&mut out, signer);
+    }
+    if !command.verifiers.is_empty() {
+        let _ = writeln!(out, "Verifiers:");
+        for verifier in &command.verifiers {
+            let _ = writeln!(out, "  {}", verifier.name);
+            for cap in &verifier.clist {
+                let _ = writeln!(out, "    - {}", describe_capability(cap));
+            }
+        }
+    }
+
+    let warnings = warnings(command);
+    if !warnings.is_empty() {
+        let _ = writeln!(out, "Warnings:");
+        for w in warnings {
+            let _ = writeln!(out, "  ! {}", w);
+        }
+    }
+    out
+}
+
+/// Plain-language description of a capability, e.g. `TRANSFER 2.0 KDA from k:ab.. to k:cd..`.
+pub fn describe_capability(cap: &Capability) -> String {
+    let (module, short) = cap.name.rsplit_once('.').unwrap_or(("", cap.name.as_str()));
+    let token = if module == "coin" { "KDA".to_string() } else { module.to_string() };
+    let arg = |i: usize| cap.args.get(i).map(arg_text).unwrap_or_else(|| "?".to_string());
+    match (short, cap.args.len()) {
+        ("GAS", 0) if module == "coin" => "pay gas".to_string(),
+        ("TRANSFER", 3) => format!("TRANSFER {} {} from {} to {}", arg(2), token, arg(0), arg(1)),
+        ("TRANSFER_XCHAIN", 4) => format!("TRANSFER_XCHAIN {} {} from {} to {} on chain {}", arg(2), token, arg(0), arg(1), arg(3)),
+        ("ROTATE", 1) => format!("ROTATE the guard of {} account {}", token, arg(0)),
+        ("GAS_PAYER", 3) => format!("GAS_PAYER: {} pays gas for {} (up to {} gas at {})", module, arg(0), arg(1), arg(2)),
+        _ if cap.args.is_empty() => cap.name.clone(),
+        _ => format!("{} {}", cap.name, cap.args.iter().map(arg_text).collect::<Vec<_>>().join(" ")),
+    }
+}
+
+fn explain_signer(out: &mut String, signer: &Signer) {
+    let mut who = signer.pub_key.clone();
+    if let Some(addr) = &signer.addr {
+        let _ = write!(who, " (address {})", addr);
+    }
+    if let Some(scheme) = signer.scheme {
+        let _ = write!(who, " [{:?}]", scheme);
+    }
+    match signer.clist.as_deref() {
+        None | Some([]) => {
+            let _ = writeln!(out, "  {}: UNSCOPED, authorizes anything the code does", who);
+        }
+        Some(caps) => {
+            let _ = writeln!(out, "  {}:", who);
+            for cap in caps {
+                let _ = writeln!(out, "    - {}", describe_capability(cap));
+            }
+        }
+    }
+}
+
+fn explain_data(out: &mut String, data: &Value) {
+    let Some(obj) = data.as_object().filter(|o| !o.is_empty()) else {
+        return;
+    };
+    let _ = writeln!(out, "Data:");
+    for (key, value) in obj {
+        match keyset_text(value) {
+            Some(ks) => { let _ = writeln!(out, "  {}: keyset {}", key, ks); }
+            None => { let _ = writeln!(out, "  {}: {}", key, value); }
+        }
+    }
+}
+
+// {"keys": [...], "pred": "keys-all"} -> "keys-all of [k1, k2]"
+fn keyset_text(value: &Value) -> Option<String> {
+    let keys = value.get("keys")?.as_array()?;
+    let keys: Vec<&str> = keys.iter().filter_map(|k| k.as_str()).collect();
+    let pred = value.get("pred").and_then(|p| p.as_str()).unwrap_or("keys-all");
+    Some(format!("{} of [{}]", pred, keys.join(", ")))
+}
+
+fn arg_text(arg: &Value) -> String {
+    match arg {
+        Value::Object(o) if o.len() == 1 && o.contains_key("decimal") => serde_json::from_value::<PactDecimal>(arg.clone())
+            .map(|d| d.to_pact())
+            .unwrap_or_else(|_| arg.to_string()),
+        Value::Number(n) if n.is_f64() => decimal_text(n.as_f64().unwrap_or_default()),
+        Value::String(s) => s.clone(),
+        Value::Object(o) if o.len() == 1 && o.contains_key("int") => o["int"].to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn decimal_text(value: f64) -> String {
+    PactDecimal::from_f64(value).map(|d| d.to_pact()).unwrap_or_else(|_| value.to_string())
+}
+
+fn timestamp(secs: u64) -> String {
+    DateTime::from_timestamp(secs as i64, 0).map(|t| t.to_rfc3339()).unwrap_or_else(|| secs.to_string())
+}
+
+fn warnings(command: &Command) -> Vec<String> {
+    let mut warnings: Vec<String> = command.signers.iter()
+        .filter(|s| s.clist.as_ref().is_none_or(|c| c.is_empty()))
+        .map(|s| format!("signer {} is unscoped: its signature can authorize any capability", s.pub_key))
+        .collect();
+    if command.signers.is_empty() && command.verifiers.is_empty() {
+        warnings.push("no signers: only code that needs no authorization will succeed".to_string());
+    }
+    let policy = default_policy();
+    if policy.is_expired(command.meta.creation_time, command.meta.ttl) {
+        warnings.push("command has already expired".to_string());
+    }
+    let cfg = ValidationConfig { time_policy: policy, ..ValidationConfig::default() };
+    warnings.extend(validate_command(command, None, &cfg).into_iter().map(|i| i.message));
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{PublicMeta, Verifier};
+    use serde_json::json;
+
+    fn command() -> Command {
+        Command {
+            meta: PublicMeta::new("k:ab", "1", 0.00000001, 2500, 1_700_000_000, 600),
+            network_id: Some("testnet04".to_string()),
+            nonce: "n".to_string(),
+            payload: Payload::exec("(coin.transfer \"k:ab\" \"k:cd\" 2.0)", json!({"ks": {"keys": ["cd"], "pred": "keys-any"}, "n": 1})),
+            signers: vec![
+                Signer::new("ab", Some(vec![Capability::gas(), Capability::transfer("k:ab", "k:cd", PactDecimal::from(2))])),
+                Signer::new("ef", None),
+            ],
+            verifiers: vec![],
+        }
+    }
+
+    #[test]
+    fn explains_costs_code_data_and_signers() {
+        assert_eq!(explain_command(&command()), [
+            "Network:  testnet04",
+            "Chain:    1",
+            "Sender:   k:ab",
+            "Gas:      up to 0.000025 KDA (2500 gas at 0.00000001 KDA)",
+            "Created:  2023-11-14T22:13:20+00:00",
+            "Expires:  2023-11-14T22:23:20+00:00 (ttl 600s)",
+            "Nonce:    n",
+            "Code:",
+            "  (coin.transfer \"k:ab\" \"k:cd\" 2.0)",
+            "Data:",
+            "  ks: keyset keys-any of [cd]",
+            "  n: 1",
+            "Signers:",
+            "  ab:",
+            "    - pay gas",
+            "    - TRANSFER 2.0 KDA from k:ab to k:cd",
+            "  ef: UNSCOPED, authorizes anything the code does",
+            "Warnings:",
+            "  ! signer ef is unscoped: its signature can authorize any capability",
+            "  ! command has already expired",
+            "",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn explains_prepared_values_continuations_and_verifiers() {
+        let mut cmd = command();
+        cmd.payload = Payload::cont("pid", true, 1, Some("p".to_string()), json!({}));
+        cmd.signers.clear();
+        cmd.verifiers = vec![Verifier::new("hyperlane_v3_message", json!("proof"), vec![Capability::new("free.bridge.MINT", vec![json!({"int": 5})])])];
+        let text = explain(&json!({"cmd": cmd.to_cmd_string(), "hash": "h", "sigs": []})).unwrap();
+        assert!(text.contains("Roll back step 1 of pact pid (with SPV proof)\nSigners:\n  (none)\nVerifiers:\n  hyperlane_v3_message\n    - free.bridge.MINT 5\n"), "{}", text);
+        assert_eq!(explain(&json!(cmd.to_cmd_string())).unwrap(), text);
+        assert!(explain(&json!({"hash": "h"})).is_err());
+        assert!(explain(&json!("{")).unwrap_err().starts_with("malformed cmd"));
+    }
+
+    #[test]
+    fn describes_well_known_capabilities() {
+        let cases = [
+            (Capability::fungible_transfer_xchain("free.tok", "a", "b", PactDecimal::parse("0.5").unwrap(), "2"), "TRANSFER_XCHAIN 0.5 free.tok from a to b on chain 2"),
+            (Capability::new("coin.ROTATE", vec![json!("k:a")]), "ROTATE the guard of KDA account k:a"),
+            (Capability::gas_payer("free.station", "k:a", 1000, PactDecimal::parse("0.00000001").unwrap()), "GAS_PAYER: free.station pays gas for k:a (up to 1000 gas at 0.00000001)"),
+            (Capability::new("free.m.CAP", vec![json!(1.5), json!(true)]), "free.m.CAP 1.5 true"),
+        ];
+        for (cap, text) in cases {
+            assert_eq!(describe_capability(&cap), text);
+        }
+    }
+}
@@ -1,4 +1,8 @@
-use crate::api::{mk_public_send, prepare_exec_cmd, prepare_cont_cmd};
+use crate::api::{cmd_expiry_time, mk_public_send, prepare_exec_cmd_with_verifiers, prepare_cont_cmd_with_verifiers};
+use crate::clock::{default_policy, TimePolicy};
+use crate::nonce::next_nonce;
+use crate::validate::{issues_to_error, validate_send_body, ValidationConfig};
+use crate::types::{Capability, Verifier};
 use crate::utils::{unique, get_headers, parse_res, KeyPair};
 use serde_json::{Value, json};
 use reqwest::blocking::Client;
@@ -25,14 +29,42 @@ pub fn simple_listen_req_from_exec(exec_msg: &Value) -> Value {
     json!({"listen": hsh})
 }
 
-fn value_to_keypair(v: &Value) -> KeyPair {
-    KeyPair {
+fn value_to_keypair(v: &Value) -> Result<KeyPair, String> {
+    let scheme = match v.get("scheme") {
+        Some(x) if !x.is_null() => Some(serde_json::from_value(x.clone()).map_err(|_| format!("unknown signer scheme {}, expected \"ED25519\" or \"WebAuthn\"", x))?),
+        _ => None,
+    };
+    // a cap that fails to parse must not be dropped: that would leave the signature less scoped than intended
+    let clist = match v.get("clist") {
+        Some(Value::Array(caps)) => Some(
+            caps.iter()
+                .map(|c| serde_json::from_value(c.clone()).map_err(|e| format!("malformed capability {}, expected {{name, args}}: {}", c, e)))
+                .collect::<Result<Vec<Capability>, _>>()?,
+        ),
+        Some(x) if !x.is_null() => return Err(format!("malformed clist {}, expected an array of capabilities", x)),
+        _ => None,
+    };
+    Ok(KeyPair {
         public_key: v.get("publicKey").and_then(|x| x.as_str()).unwrap_or("").to_string(),
         secret_key: v.get("secretKey").and_then(|x| x.as_str()).unwrap_or("").to_string(),
-        clist: v.get("clist").and_then(|c| c.as_array()).map(|arr| arr.clone()),
-    }
+        clist,
+        scheme,
+        addr: v.get("addr").and_then(|x| x.as_str()).map(|s| s.to_string()),
+    })
 }
 
+fn value_to_keypairs(cmd: &Value) -> Result<Option<Vec<KeyPair>>, String> {
+    cmd.get("keyPairs").and_then(|v| v.as_array()).map(|arr| arr.iter().map(value_to_keypair).collect()).transpose()
+}
+
+fn value_to_verifiers(cmd: &Value) -> Result<Vec<Verifier>, String> {
+    cmd.get("verifiers").and_then(|v| v.as_array()).map(|arr| {
+        arr.iter().map(|v| serde_json::from_value(v.clone()).map_err(|e| format!("malformed verifier, expected {{name, proof, clist}}: {}", e))).collect()
+    }).unwrap_or_else(|| Ok(vec![]))
+}
+
+/// Sign a command object (`pactCode` or `type: "cont"`, `meta`, `keyPairs`, ..)
+/// into `{cmd, hash, sigs}`, or `{"error": ..}` if it is malformed.
 pub fn make_prepare_cmd(cmd: &Value) -> Value {
     // Cont command if type == "cont"
     if cmd.get("type").and_then(|v| v.as_str()) == Some("cont") {
@@ -44,8 +76,15 @@ pub fn make_prepare_cmd(cmd: &Value) -> Value {
         let meta = cmd.get("meta").cloned().unwrap_or(json!({}));
         let network_id = cmd.get("networkId").and_then(|v| v.as_str()).map(|s| s.to_string());
         let nonce = cmd.get("nonce").and_then(|v| v.as_str()).map(|s| s.to_string());
-        let key_pairs: Option<Vec<KeyPair>> = cmd.get("keyPairs").and_then(|v| v.as_array()).map(|arr| arr.iter().map(value_to_keypair).collect());
-        return prepare_cont_cmd(pact_id, rollback, step, proof, env_data, meta, network_id, nonce, key_pairs);
+        let key_pairs = match value_to_keypairs(cmd) {
+            Ok(key_pairs) => key_pairs,
+            Err(e) => return json!({"error": e}),
+        };
+        let verifiers = match value_to_verifiers(cmd) {
+            Ok(verifiers) => verifiers,
+            Err(e) => return json!({"error": e}),
+        };
+        return prepare_cont_cmd_with_verifiers(pact_id, rollback, step, proof, env_data, meta, network_id, nonce, key_pairs, verifiers);
     }
     // Exec command default
     let pact_code = cmd.get("pactCode").and_then(|v| v.as_str()).unwrap_or("");
@@ -53,22 +92,43 @@ pub fn make_prepare_cmd(cmd: &Value) -> Value {
     let meta = cmd.get("meta").cloned().unwrap_or(json!({}));
     let network_id = cmd.get("networkId").and_then(|v| v.as_str()).map(|s| s.to_string());
     let nonce = cmd.get("nonce").and_then(|v| v.as_str()).map(|s| s.to_string());
-    let key_pairs: Option<Vec<KeyPair>> = cmd.get("keyPairs").and_then(|v| v.as_array()).map(|arr| arr.iter().map(value_to_keypair).collect());
-    prepare_exec_cmd(pact_code, env_data, meta, network_id, nonce, key_pairs)
+    let key_pairs = match value_to_keypairs(cmd) {
+        Ok(key_pairs) => key_pairs,
+        Err(e) => return json!({"error": e}),
+    };
+    let verifiers = match value_to_verifiers(cmd) {
+        Ok(verifiers) => verifiers,
+        Err(e) => return json!({"error": e}),
+    };
+    prepare_exec_cmd_with_verifiers(pact_code, env_data, meta, network_id, nonce, key_pairs, verifiers)
 }
 
-pub fn fetch_send_raw(send_cmd: &Value, api_host: &str, debug: bool) -> reqwest::blocking::Response {
-    let client = create_http_client();
-    // If already in form {"cmds": [...]}, assume pre-prepared
-    let prepared_cmds: Vec<Value> = if let Some(arr) = send_cmd.get("cmds").and_then(|v| v.as_array()) {
+// The first command that could not be prepared, as its `{"error": ..}`
+fn prepare_error(prepared: &[Value]) -> Option<Value> {
+    prepared.iter().find(|c| c.get("error").is_some()).cloned()
+}
+
+/// Signed commands for a send request: `{"cmds": [...]}` is taken as already
+/// prepared, a command object or an array of them is signed via `make_prepare_cmd`.
+pub fn prepare_send_cmds(send_cmd: &Value) -> Vec<Value> {
+    if let Some(arr) = send_cmd.get("cmds").and_then(|v| v.as_array()) {
         arr.to_vec()
     } else if send_cmd.is_array() {
         send_cmd.as_array().unwrap().iter().map(make_prepare_cmd).collect()
     } else {
         vec![make_prepare_cmd(send_cmd)]
-    };
+    }
+}
+
+pub fn fetch_send_raw(send_cmd: &Value, api_host: &str, debug: bool) -> reqwest::blocking::Response {
+    post_prepared(prepare_send_cmds(send_cmd), api_host, debug)
+}
+
+// POST commands prepared once by the caller to /send, so what was checked is what is sent
+fn post_prepared(prepared_cmds: Vec<Value>, api_host: &str, debug: bool) -> reqwest::blocking::Response {
+    let client = create_http_client();
     if debug { println!("prepared_cmds: {:?}", prepared_cmds); }
-    client.post(&format!("{}/api/v1/send", api_host))
+    client.post(format!("{}/api/v1/send", api_host))
         .json(&mk_public_send(prepared_cmds))
         .headers(get_headers())
         .send()
@@ -76,13 +136,232 @@ pub fn fetch_send_raw(send_cmd: &Value, api_host: &str, debug: bool) -> reqwest:
 }
 
 pub fn send(send_cmd: &Value, api_host: &str, debug: bool) -> Value {
-    let res = fetch_send_raw(send_cmd, api_host, debug);
-    parse_res(res)
+    let prepared = prepare_send_cmds(send_cmd);
+    if let Some(error) = prepare_error(&prepared) {
+        return error;
+    }
+    parse_res(post_prepared(prepared, api_host, debug))
+}
+
+/// `send`, but the prepared commands are first checked with `validate::validate_send_body`;
+/// if any problem is found nothing is sent and `{"error": ..., "issues": [...]}` is returned.
+pub fn send_validated(send_cmd: &Value, api_host: &str, cfg: &ValidationConfig, debug: bool) -> Value {
+    let prepared = prepare_send_cmds(send_cmd);
+    if let Some(error) = prepare_error(&prepared) {
+        return error;
+    }
+    let issues = validate_send_body(&mk_public_send(prepared.clone()), Some(api_host), cfg);
+    if !issues.is_empty() {
+        return issues_to_error(&issues);
+    }
+    parse_res(post_prepared(prepared, api_host, debug))
+}
+
+/// Retry settings for `send_idempotent`.
+///
+///  attempts          -> send attempts before giving up (default 3)
+///  retry_interval_ms -> pause between attempts (default 2000)
+#[derive(Debug, Clone)]
+pub struct IdempotentSendConfig {
+    pub attempts: u32,
+    pub retry_interval_ms: u64,
+}
+
+impl Default for IdempotentSendConfig {
+    fn default() -> Self {
+        Self { attempts: 3, retry_interval_ms: 2000 }
+    }
+}
+
+/// Send that is safe to repeat.
+///
+/// The commands are prepared (signed) once and their request keys computed
+/// locally from the hashes. Before every attempt the keys are looked up with
+/// /poll and, where the host serves it, in the mempool (a failed mempool
+/// lookup counts as "not pending"); only commands the node does not know yet are
+/// (re)sent, always as the identical signed bytes, so a command never reaches
+/// the chain twice. Returns `{"requestKeys": [...]}` for all commands whether
+/// they were sent now or earlier, or `{"error": ..., "requestKeys": [...]}`
+/// once the attempts are exhausted.
+///
+/// Pass the same prepared `{"cmds": [...]}` body again to resume after an error.
+pub fn send_idempotent(send_cmd: &Value, api_host: &str, cfg: &IdempotentSendConfig) -> Value {
+    let prepared = prepare_send_cmds(send_cmd);
+    if let Some(error) = prepare_error(&prepared) {
+        return error;
+    }
+    let request_keys: Vec<String> = prepared.iter().filter_map(|c| c.get("hash").and_then(|h| h.as_str()).map(|h| h.to_string())).collect();
+    if request_keys.len() != prepared.len() {
+        return json!({"error": "prepared command without hash"});
+    }
+    let mut last_error = String::new();
+    for attempt in 1..=cfg.attempts.max(1) {
+        if attempt > 1 {
+            std::thread::sleep(std::time::Duration::from_millis(cfg.retry_interval_ms));
+        }
+        let known = match known_request_keys(&request_keys, api_host) {
+            Ok(known) => known,
+            Err(e) => { last_error = e; continue; }
+        };
+        let pending: Vec<Value> = prepared.iter().zip(&request_keys).filter(|(_, rk)| !known.contains(*rk)).map(|(c, _)| c.clone()).collect();
+        if pending.is_empty() {
+            return json!({"requestKeys": request_keys});
+        }
+        match try_post(&format!("{}/api/v1/send", api_host), &mk_public_send(pending)) {
+            Ok(res) if res.get("requestKeys").is_some() => return json!({"requestKeys": request_keys}),
+            Ok(res) => last_error = res.to_string(),
+            Err(e) => last_error = e,
+        }
+    }
+    json!({"error": format!("send failed after {} attempts: {}", cfg.attempts.max(1), last_error), "requestKeys": request_keys})
+}
+
+/// Settings for `send_refreshing`.
+///
+///  attempts           -> send attempts before giving up (default 3)
+///  retry_interval_ms  -> pause between attempts (default 2000)
+///  min_remaining_secs -> a command with less time to live than this is rebuilt before sending (default 60)
+///  time_policy        -> clock, skew and ttl for rebuilt commands (default `clock::default_policy()`)
+#[derive(Debug, Clone)]
+pub struct RefreshSendConfig {
+    pub attempts: u32,
+    pub retry_interval_ms: u64,
+    pub min_remaining_secs: u64,
+    pub time_policy: TimePolicy,
+}
+
+impl Default for RefreshSendConfig {
+    fn default() -> Self {
+        Self { attempts: 3, retry_interval_ms: 2000, min_remaining_secs: 60, time_policy: default_policy() }
+    }
+}
+
+/// Copy of a command object (pactCode/envData/meta/keyPairs) with a fresh
+/// creationTime, ttl and nonce, to be signed again by its keyPairs.
+pub fn refresh_command(cmd: &Value, policy: &TimePolicy) -> Value {
+    let mut fresh = cmd.clone();
+    if let Some(meta) = fresh.get_mut("meta").and_then(|m| m.as_object_mut()) {
+        meta.insert("creationTime".to_string(), json!(policy.creation_time()));
+        meta.insert("ttl".to_string(), json!(policy.ttl));
+    }
+    fresh["nonce"] = json!(next_nonce());
+    fresh
+}
+
+/// Send a command object, rebuilding it when it has expired or is about to.
+///
+/// A command that would reach the node with less than `min_remaining_secs`
+/// to live gets a fresh creationTime/nonce and is signed again by the same
+/// keyPairs. Failed sends are retried with the identical signed bytes while
+/// the command is alive; once it has expired, and /poll shows it was never
+/// mined, it is rebuilt too, so a retry can never execute twice.
+///
+/// Returns the node's response for the command that was accepted, whose
+/// `requestKeys` are the new ones, plus `previousRequestKeys` listing the
+/// request keys of commands that were replaced. Errors come back inline as
+/// `{"error": ..., "previousRequestKeys": [...]}`.
+pub fn send_refreshing(cmd: &Value, api_host: &str, cfg: &RefreshSendConfig) -> Value {
+    send_refreshing_tracked(cmd, api_host, cfg).0
+}
+
+// `send_refreshing`, also returning the command object that was last sent
+pub(crate) fn send_refreshing_tracked(cmd: &Value, api_host: &str, cfg: &RefreshSendConfig) -> (Value, Value) {
+    if cmd.get("cmds").is_some() || cmd.is_array() {
+        return (json!({"error": "send_refreshing needs a single command object with keyPairs, not signed commands"}), cmd.clone());
+    }
+    if let Err(e) = cfg.time_policy.validate_ttl(cfg.time_policy.ttl) {
+        return (json!({"error": e}), cmd.clone());
+    }
+    let mut bag = cmd.clone();
+    let mut signed = make_prepare_cmd(&bag);
+    if let Some(error) = signed.get("error") {
+        return (json!({"error": error}), bag);
+    }
+    let mut sent = false;
+    let mut previous: Vec<String> = Vec::new();
+    let mut last_error = String::new();
+    for attempt in 1..=cfg.attempts.max(1) {
+        if attempt > 1 {
+            std::thread::sleep(std::time::Duration::from_millis(cfg.retry_interval_ms));
+        }
+        let remaining = cmd_expiry_time(&signed).map(|t| t.saturating_sub(cfg.time_policy.now())).unwrap_or(0);
+        let rebuild = if !sent {
+            remaining < cfg.min_remaining_secs
+        } else if remaining == 0 {
+            // An expired command that was sent may still have been mined just in time
+            let rk = request_key_of(&signed);
+            match try_post(&format!("{}/api/v1/poll", api_host), &json!({"requestKeys": [rk]})) {
+                Ok(res) if res.get(rk.as_str()).is_some() => return (json!({"requestKeys": [rk], "previousRequestKeys": previous}), bag),
+                Ok(_) => true,
+                Err(e) => { last_error = e; continue; }
+            }
+        } else {
+            false
+        };
+        if rebuild {
+            if sent {
+                previous.push(request_key_of(&signed));
+            }
+            bag = refresh_command(&bag, &cfg.time_policy);
+            signed = make_prepare_cmd(&bag);
+            if let Some(error) = signed.get("error") {
+                return (json!({"error": error, "previousRequestKeys": previous}), bag);
+            }
+        }
+        sent = true;
+        match try_post(&format!("{}/api/v1/send", api_host), &mk_public_send(vec![signed.clone()])) {
+            Ok(mut res) if res.get("requestKeys").is_some() => {
+                res["previousRequestKeys"] = json!(previous);
+                return (res, bag);
+            }
+            Ok(res) => last_error = res.to_string(),
+            Err(e) => last_error = e,
+        }
+    }
+    (json!({"error": format!("send failed after {} attempts: {}", cfg.attempts.max(1), last_error), "previousRequestKeys": previous}), bag)
+}
+
+fn request_key_of(signed: &Value) -> String {
+    signed.get("hash").and_then(|h| h.as_str()).unwrap_or_default().to_string()
+}
+
+// Request keys that are already mined (/poll) or pending in the mempool
+fn known_request_keys(request_keys: &[String], api_host: &str) -> Result<Vec<String>, String> {
+    let polled = try_post(&format!("{}/api/v1/poll", api_host), &json!({"requestKeys": request_keys}))?;
+    let mut known: Vec<String> = request_keys.iter().filter(|rk| polled.get(rk.as_str()).is_some()).cloned().collect();
+    // chainweb serves mempool lookup (.../chain/<id>/mempool/lookup) on its P2P API,
+    // which most service hosts don't expose; without it only /poll decides
+    if let Some(chain_url) = api_host.strip_suffix("/pact") {
+        if let Ok(lookup) = try_post(&format!("{}/mempool/lookup", chain_url), &json!(request_keys)) {
+            let tags = lookup.as_array().cloned().unwrap_or_default();
+            for (rk, tag) in request_keys.iter().zip(&tags) {
+                if tag.get("tag").and_then(|t| t.as_str()) == Some("Pending") && !known.contains(rk) {
+                    known.push(rk.clone());
+                }
+            }
+        }
+    }
+    Ok(known)
+}
+
+// POST without panicking on transport errors; non-2xx responses are errors too
+pub(crate) fn try_post(url: &str, body: &Value) -> Result<Value, String> {
+    let res = create_http_client().post(url)
+        .json(body)
+        .headers(get_headers())
+        .send()
+        .map_err(|e| format!("request to {} failed: {}", url, e))?;
+    let status = res.status();
+    let parsed = parse_res(res);
+    if !status.is_success() {
+        return Err(format!("{} returned {}: {}", url, status.as_u16(), parsed));
+    }
+    Ok(parsed)
 }
 
 pub fn fetch_spv_raw(spv_cmd: &Value, api_host: &str) -> reqwest::blocking::Response {
     let client = create_http_client();
-    client.post(&format!("{}/spv", api_host))
+    client.post(format!("{}/spv", api_host))
         .json(spv_cmd)
         .headers(get_headers())
         .send()
@@ -94,8 +373,7 @@ pub fn spv(spv_cmd: &Value, api_host: &str) -> Value {
     parse_res(res)
 }
 
-pub fn fetch_local_raw(local_cmd: &Value, api_host: &str, options: Option<LocalOptions>) -> reqwest::blocking::Response {
-    let client = create_http_client();
+fn local_url(api_host: &str, options: Option<LocalOptions>) -> String {
     let mut url = format!("{}/api/v1/local", api_host);
     
     // Build query string based on provided options
@@ -109,25 +387,41 @@ pub fn fetch_local_raw(local_cmd: &Value, api_host: &str, options: Option<LocalO
         }
         
         if !query_params.is_empty() {
-            url.push_str("?");
+            url.push('?');
             url.push_str(&query_params.join("&"));
         }
     }
-    
+    url
+}
+
+pub fn fetch_local_raw(local_cmd: &Value, api_host: &str, options: Option<LocalOptions>) -> reqwest::blocking::Response {
     // Match Python implementation: prepare the command before posting
-    let prepared_cmd = make_prepare_cmd(local_cmd);
-    
+    post_local_prepared(&make_prepare_cmd(local_cmd), api_host, options)
+}
+
+// POST a prepared {cmd, hash, sigs} object to /local as-is
+fn post_local_prepared(prepared_cmd: &Value, api_host: &str, options: Option<LocalOptions>) -> reqwest::blocking::Response {
+    let client = create_http_client();
+    let url = local_url(api_host, options);
     client.post(&url)
-        .json(&prepared_cmd)
+        .json(prepared_cmd)
         .headers(get_headers())
         .send()
         .expect("Failed to send request")
 }
 
+// Post an already prepared {cmd, hash, sigs} object to /local as-is
+pub fn local_signed(signed_cmd: &Value, api_host: &str, options: Option<LocalOptions>) -> Value {
+    parse_res(post_local_prepared(signed_cmd, api_host, options))
+}
+
 // Primary function with options struct
 pub fn local_with_opts(local_cmd: &Value, api_host: &str, options: Option<LocalOptions>) -> Value {
-    let res = fetch_local_raw(local_cmd, api_host, options);
-    parse_res(res)
+    let prepared = make_prepare_cmd(local_cmd);
+    if let Some(error) = prepare_error(std::slice::from_ref(&prepared)) {
+        return error;
+    }
+    parse_res(post_local_prepared(&prepared, api_host, options))
 }
 
 // Default function without options (backward compatible)
@@ -150,7 +444,7 @@ pub fn local_with_options(local_cmd: &Value, api_host: &str, preflight: Option<b
 
 pub fn fetch_poll_raw(poll_cmd: &Value, api_host: &str) -> reqwest::blocking::Response {
     let client = create_http_client();
-    client.post(&format!("{}/api/v1/poll", api_host))
+    client.post(format!("{}/api/v1/poll", api_host))
         .json(poll_cmd)
         .headers(get_headers())
         .send()
@@ -164,7 +458,7 @@ pub fn poll(poll_cmd: &Value, api_host: &str) -> Value {
 
 pub fn fetch_listen_raw(listen_cmd: &Value, api_host: &str) -> reqwest::blocking::Response {
     let client = create_http_client();
-    client.post(&format!("{}/api/v1/listen", api_host))
+    client.post(format!("{}/api/v1/listen", api_host))
         .json(listen_cmd)
         .headers(get_headers())
         .send()
@@ -179,7 +473,7 @@ pub fn listen(listen_cmd: &Value, api_host: &str) -> Value {
 pub fn send_signed(signed_cmd: &Value, api_host: &str) -> Value {
     let client = Client::new();
     let cmd = json!({"cmds": [signed_cmd]});
-    let res = client.post(&format!("{}/api/v1/send", api_host))
+    let res = client.post(format!("{}/api/v1/send", api_host))
         .json(&cmd)
         .headers(get_headers())
         .send()
@@ -0,0 +1,405 @@
+// Format module: pretty-printer for Pact source and generated expressions
+use crate::expr::{escape_string, PactDef, PactExpr};
+use crate::parser::{parse_with_comments, Comment, ParseError, Span, Spanned};
+
+/// Layout of formatted code.
+///
+///  width  -> preferred maximum line width (default 80)
+///  indent -> spaces per nesting level (default 2)
+#[derive(Debug, Clone)]
+pub struct FormatConfig {
+    pub width: usize,
+    pub indent: usize,
+}
+
+impl Default for FormatConfig {
+    fn default() -> Self {
+        Self { width: 80, indent: 2 }
+    }
+}
+
+/// Reformat Pact source.
+///
+/// A form that fits on the rest of its line is printed on one line;
+/// otherwise its arguments go on their own lines, indented one level, with
+/// leading arguments such as a `defun`'s name and parameters or `with-read`'s
+/// table, key and bindings kept on the first line. Comments are kept, as are
+/// single blank lines between forms, and literals are copied as written
+/// (`1.0000` stays `1.0000`). Formatting formatted code changes nothing.
+///
+/// ```text
+/// format_source("(defun f (a) (enforce (> a 0) \"positive\") (+ a 1))", &FormatConfig { width: 30, ..Default::default() })?
+/// // (defun f (a)
+/// //   (enforce (> a 0) "positive")
+/// //   (+ a 1))
+/// ```
+pub fn format_source(src: &str, cfg: &FormatConfig) -> Result<String, ParseError> {
+    let (forms, comments) = parse_with_comments(src)?;
+    let mut printer = Printer::new(cfg, src, &comments);
+    for form in &forms {
+        printer.item_start(form.span, 0);
+        printer.print(form);
+        printer.trailing(form.span);
+    }
+    printer.comments_before(usize::MAX, 0);
+    Ok(printer.finish())
+}
+
+/// Format an expression, e.g. code built with `PactExpr`. No trailing newline.
+pub fn format_expr(expr: &PactExpr, cfg: &FormatConfig) -> String {
+    let mut printer = Printer::new(cfg, "", &[]);
+    printer.print(&unspanned(expr));
+    printer.finish().trim_end().to_string()
+}
+
+// A node for `expr` without source positions
+fn unspanned(expr: &PactExpr) -> Spanned {
+    Spanned { expr: expr.clone(), span: Span::default(), children: expr.children().into_iter().map(unspanned).collect() }
+}
+
+// Arguments kept on the first line of a broken application, by function
+fn header_args(head: &PactExpr) -> usize {
+    let PactExpr::Symbol(name) = head else { return 0 };
+    match name.as_str() {
+        "with-default-read" => 4,
+        "with-read" => 3,
+        "module" | "bind" | "update" | "insert" | "write" => 2,
+        "interface" | "defconst" | "defproperty" | "with-capability" | "if" | "enforce" | "enforce-one" | "map"
+        | "fold" | "filter" | "at" | "read" | "namespace" | "use" | "implements" => 1,
+        _ => 0,
+    }
+}
+
+// `(defun name:type (params..)`, up to the body
+fn def_header(def: &PactDef) -> String {
+    let mut header = format!("({} {}", def.kind.keyword(), def.name);
+    if let Some(ty) = &def.ty {
+        header.push_str(&format!(":{}", ty));
+    }
+    if let Some(params) = &def.params {
+        let params: Vec<String> = params.iter().map(|(n, t)| match t {
+            Some(t) => format!("{}:{}", n, t),
+            None => n.clone(),
+        }).collect();
+        header.push_str(&format!(" ({})", params.join(" ")));
+    }
+    header
+}
+
+struct Printer<'a> {
+    cfg: &'a FormatConfig,
+    src: &'a str,
+    comments: &'a [Comment],
+    next_comment: usize,
+    out: String,
+    col: usize,
+    // source line of what was printed last, to keep blank lines
+    last_line: usize,
+    // the current line ends in a comment, so nothing may follow on it
+    after_comment: bool,
+}
+
+impl<'a> Printer<'a> {
+    fn new(cfg: &'a FormatConfig, src: &'a str, comments: &'a [Comment]) -> Self {
+        Printer { cfg, src, comments, next_comment: 0, out: String::new(), col: 0, last_line: 0, after_comment: false }
+    }
+
+    fn finish(self) -> String {
+        let out = self.out.trim_start_matches('\n').trim_end();
+        if out.is_empty() {
+            String::new()
+        } else {
+            format!("{}\n", out)
+        }
+    }
+
+    fn write(&mut self, s: &str) {
+        self.out.push_str(s);
+        self.col += s.chars().count();
+    }
+
+    // New line at `indent`, after a blank one if the source had one before `next_line`
+    fn line_break(&mut self, indent: usize, next_line: usize) {
+        self.out.push('\n');
+        if self.last_line > 0 && next_line > self.last_line + 1 {
+            self.out.push('\n');
+        }
+        self.out.push_str(&" ".repeat(indent));
+        self.col = indent;
+        self.after_comment = false;
+    }
+
+    fn has_comment(&self, span: Span) -> bool {
+        self.comments[self.next_comment..].iter().any(|c| c.span.start >= span.start && c.span.start < span.end)
+    }
+
+    // Comments before byte `end`, each on its own line
+    fn comments_before(&mut self, end: usize, indent: usize) {
+        while let Some(c) = self.comments.get(self.next_comment).filter(|c| c.span.start < end) {
+            self.line_break(indent, c.span.line);
+            self.write(&c.text);
+            self.after_comment = true;
+            self.last_line = c.span.end_line;
+            self.next_comment += 1;
+        }
+    }
+
+    // Start an item on a new line, after the comments that precede it
+    fn item_start(&mut self, span: Span, indent: usize) {
+        self.comments_before(span.start, indent);
+        self.line_break(indent, span.line);
+    }
+
+    // A comment right after the item spanning `span`, on the same line, stays there
+    fn trailing(&mut self, span: Span) {
+        self.last_line = self.last_line.max(span.end_line);
+        let follows = |c: &&Comment| {
+            c.span.line == span.end_line
+                && self.src.get(span.end..c.span.start).is_some_and(|between| between.chars().all(|ch| ch.is_whitespace() || ch == ','))
+        };
+        if let Some(c) = self.comments.get(self.next_comment).filter(follows) {
+            self.write(" ");
+            self.write(&c.text);
+            self.after_comment = true;
+            self.next_comment += 1;
+        }
+    }
+
+    // Comments left inside the form, then its closing bracket
+    fn close(&mut self, bracket: &str, indent: usize, inner_indent: usize, end: usize) {
+        self.comments_before(end, inner_indent);
+        if self.after_comment {
+            self.line_break(indent, 0);
+        }
+        self.write(bracket);
+    }
+
+    // `span` of a let binding's value extended over the binding's closing paren
+    fn binding_span(&self, span: Span) -> Span {
+        let rest = self.src.get(span.end..).unwrap_or("");
+        match rest.trim_start().strip_prefix(')') {
+            Some(after) => Span { end: self.src.len() - after.len(), ..span },
+            None => span,
+        }
+    }
+
+    fn fits(&self, node: &Spanned, flat: &str) -> bool {
+        self.col + flat.chars().count() <= self.cfg.width && !self.has_comment(node.span)
+    }
+
+    // `node` on one line. Tokens are copied from the source, so literals stay
+    // as written (`1.0000`, `"\65"`); without source they are rendered.
+    fn flat(&self, node: &Spanned) -> String {
+        let flats = |nodes: &[Spanned]| nodes.iter().map(|n| self.flat(n)).collect::<Vec<_>>();
+        match &node.expr {
+            PactExpr::Symbol(_) | PactExpr::Qualified { .. } | PactExpr::String(_) | PactExpr::Integer(_) | PactExpr::Decimal(_)
+            | PactExpr::Bool(_) | PactExpr::Typed { .. }
+                if !self.src.is_empty() =>
+            {
+                node.span.text(self.src).to_string()
+            }
+            PactExpr::App(..) => format!("({})", flats(&node.children).join(" ")),
+            PactExpr::List(_) => format!("[{}]", flats(&node.children).join(" ")),
+            PactExpr::Object(fields) | PactExpr::Bindings(fields) => {
+                let sep = if matches!(node.expr, PactExpr::Object(_)) { ": " } else { " := " };
+                let entries: Vec<String> = fields.iter().zip(flats(&node.children)).map(|((k, _), v)| format!("{}{}{}", escape_string(k), sep, v)).collect();
+                format!("{{{}}}", entries.join(", "))
+            }
+            PactExpr::Let(bindings, _) | PactExpr::LetStar(bindings, _) => {
+                let keyword = if matches!(node.expr, PactExpr::Let(..)) { "let" } else { "let*" };
+                let values = flats(&node.children);
+                let (bound, body) = values.split_at(bindings.len());
+                let bound: Vec<String> = bindings.iter().zip(bound).map(|((name, _), value)| format!("({} {})", name, value)).collect();
+                let body: String = body.iter().map(|b| format!(" {}", b)).collect();
+                format!("({} ({}){})", keyword, bound.join(" "), body)
+            }
+            PactExpr::Meta(name, _) => std::iter::once(format!("@{}", name)).chain(flats(&node.children)).collect::<Vec<_>>().join(" "),
+            PactExpr::Def(def) => {
+                let body: String = flats(&node.children).iter().map(|b| format!(" {}", b)).collect();
+                format!("{}{})", def_header(def), body)
+            }
+            _ => node.expr.to_string(),
+        }
+    }
+
+    fn print(&mut self, node: &Spanned) {
+        let flat = self.flat(node);
+        let forced = matches!(&node.expr, PactExpr::App(head, _) if matches!(head.as_ref(), PactExpr::Symbol(h) if h == "module" || h == "interface"));
+        if !forced && self.fits(node, &flat) {
+            self.write(&flat);
+            return;
+        }
+        let indent = self.col;
+        let body_indent = indent + self.cfg.indent;
+        match &node.expr {
+            PactExpr::App(head, _) => {
+                self.write("(");
+                self.print(&node.children[0]);
+                let mut last = node.children[0].span;
+                let mut rest = 1;
+                while rest <= header_args(head) && rest < node.children.len() {
+                    let arg = &node.children[rest];
+                    let flat = self.flat(arg);
+                    if self.col + 1 + flat.chars().count() > self.cfg.width || self.has_comment(Span { start: node.span.start, ..arg.span }) {
+                        break;
+                    }
+                    self.write(" ");
+                    self.write(&flat);
+                    last = arg.span;
+                    rest += 1;
+                }
+                self.trailing(last);
+                self.body(&node.children[rest..], body_indent);
+                self.close(")", indent, body_indent, node.span.end);
+            }
+            PactExpr::Def(def) => {
+                self.write(&def_header(def));
+                self.body(&node.children, body_indent);
+                self.close(")", indent, body_indent, node.span.end);
+            }
+            PactExpr::Let(bindings, _) | PactExpr::LetStar(bindings, _) => {
+                let keyword = if matches!(node.expr, PactExpr::Let(..)) { "(let (" } else { "(let* (" };
+                self.write(keyword);
+                let align = self.col;
+                for (i, ((name, _), value)) in bindings.iter().zip(&node.children).enumerate() {
+                    if i > 0 || self.has_comment(Span { start: node.span.start, end: value.span.start, ..value.span }) {
+                        self.item_start(value.span, align);
+                    }
+                    self.write(&format!("({} ", name));
+                    self.print(value);
+                    self.write(")");
+                    self.trailing(self.binding_span(value.span));
+                }
+                self.close(")", indent, align, node.children.get(bindings.len()).map(|b| b.span.start).unwrap_or(node.span.end));
+                self.body(&node.children[bindings.len()..], body_indent);
+                self.close(")", indent, body_indent, node.span.end);
+            }
+            PactExpr::List(_) => {
+                self.write("[");
+                self.items(&node.children, indent + 1, |_| String::new(), "");
+                self.close("]", indent, indent + 1, node.span.end);
+            }
+            PactExpr::Object(fields) | PactExpr::Bindings(fields) => {
+                let sep = if matches!(node.expr, PactExpr::Object(_)) { ": " } else { " := " };
+                self.write("{");
+                self.items(&node.children, indent + 1, |i| format!("{}{}", escape_string(&fields[i].0), sep), ",");
+                self.close("}", indent, indent + 1, node.span.end);
+            }
+            PactExpr::Meta(name, _) => {
+                self.write(&format!("@{}", name));
+                for child in &node.children {
+                    self.write(" ");
+                    self.print(child);
+                }
+            }
+            _ => self.write(&flat),
+        }
+    }
+
+    // Children of a broken form, one per line; no blank line before the first
+    fn body(&mut self, children: &[Spanned], indent: usize) {
+        self.last_line = 0;
+        for child in children {
+            self.item_start(child.span, indent);
+            self.print(child);
+            self.trailing(child.span);
+        }
+    }
+
+    // List or object entries: the first right after the bracket, the others aligned below it
+    fn items(&mut self, children: &[Spanned], indent: usize, prefix: impl Fn(usize) -> String, separator: &str) {
+        for (i, child) in children.iter().enumerate() {
+            if i > 0 || self.has_comment(Span { end: child.span.start, ..Span::default() }) {
+                self.item_start(child.span, indent);
+            }
+            self.write(&prefix(i));
+            self.print(child);
+            if i + 1 < children.len() {
+                self.write(separator);
+            }
+            self.trailing(child.span);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    const MODULE: &str = r#"; demo
+(module demo GOV
+  @doc "Demo"
+  (defcap GOV () (enforce-guard (read-keyset "ks")))
+
+  (defschema account balance:decimal guard:guard)
+  (deftable accounts:{account})
+  (defun transfer:string (from:string to:string amount:decimal)
+    @doc "move funds"
+    (with-read accounts from {"balance" := bal} ; read the sender
+      (enforce (>= bal amount) "insufficient")
+      (let* ((a 1.0) (b:integer 100000000000000000000)) (update accounts from {"balance": (- bal amount)})))))
+"#;
+
+    fn format(src: &str, width: usize) -> String {
+        format_source(src, &FormatConfig { width, ..Default::default() }).unwrap()
+    }
+
+    fn exprs(src: &str) -> Vec<PactExpr> {
+        parse(src).unwrap().into_iter().map(|f| f.expr).collect()
+    }
+
+    #[test]
+    fn short_forms_stay_on_one_line() {
+        assert_eq!(format("(+   1\n  2)", 80), "(+ 1 2)\n");
+        assert_eq!(format("", 80), "");
+    }
+
+    #[test]
+    fn long_forms_break_with_header_arguments_kept() {
+        let src = "(defun f (a) (enforce (> a 0) \"positive\") (+ a 1))";
+        assert_eq!(format(src, 30), "(defun f (a)\n  (enforce (> a 0) \"positive\")\n  (+ a 1))\n");
+        assert_eq!(format("(with-read accounts id {\"balance\" := bal} (enforce (> bal 0) \"x\"))", 50),
+            "(with-read accounts id {\"balance\" := bal}\n  (enforce (> bal 0) \"x\"))\n");
+    }
+
+    #[test]
+    fn formatting_keeps_code_and_comments_and_is_idempotent() {
+        for width in [20, 40, 80, 200] {
+            let formatted = format(MODULE, width);
+            assert_eq!(exprs(&formatted), exprs(MODULE), "width {}", width);
+            assert_eq!(format(&formatted, width), formatted, "width {}", width);
+            let comments = |src: &str| parse_with_comments(src).unwrap().1.into_iter().map(|c| c.text).collect::<Vec<_>>();
+            assert_eq!(comments(&formatted), comments(MODULE), "width {}", width);
+        }
+        assert!(format(MODULE, 80).contains("{\"balance\" := bal} ; read the sender\n"));
+    }
+
+    #[test]
+    fn literals_are_kept_as_written() {
+        let src = r#"(f 1.0000 +7 007 100000000000000000000000 "\65\&1" {"k": 0.50} (let ((a 2.10)) a))"#;
+        assert_eq!(format(src, 200), format!("{}\n", src));
+        let broken = format(src, 30);
+        for literal in ["1.0000", "+7", "007", "100000000000000000000000", r#""\65\&1""#, "0.50", "2.10"] {
+            assert!(broken.contains(literal), "{} missing from {}", literal, broken);
+        }
+        assert_eq!(format(&broken, 30), broken);
+    }
+
+    #[test]
+    fn blank_lines_between_forms_are_kept_once() {
+        assert_eq!(format("(a)\n\n\n\n(b)\n(c)", 80), "(a)\n\n(b)\n(c)\n");
+    }
+
+    #[test]
+    fn format_expr_renders_built_code() {
+        let code = PactExpr::call("coin.transfer", vec![PactExpr::string("alice"), PactExpr::string("bob"), PactExpr::integer(1)]).unwrap();
+        assert_eq!(format_expr(&code, &FormatConfig::default()), r#"(coin.transfer "alice" "bob" 1)"#);
+        assert_eq!(format_expr(&code, &FormatConfig { width: 20, ..Default::default() }), "(coin.transfer\n  \"alice\"\n  \"bob\"\n  1)");
+    }
+
+    #[test]
+    fn invalid_source_is_an_error() {
+        assert!(format_source("(defun f", &FormatConfig::default()).is_err());
+    }
+}
@@ -0,0 +1,257 @@
+// Gas module: gas limit estimation through preflight /local
+use crate::decimal::PactDecimal;
+use crate::fetch::{self, LocalOptions};
+use crate::types::{Capability, Command};
+use crate::utils::KeyPair;
+use serde_json::{json, Value};
+
+/// Chainweb block gas limit; no transaction may request more.
+pub const BLOCK_GAS_LIMIT: u64 = 150000;
+
+/// Tuning for `estimate_gas` and friends.
+///
+///  margin          -> multiplier applied to the gas consumed in preflight (default 1.2 = +20%)
+///  min_gas_limit   -> floor for the resulting limit (default 500)
+///  block_gas_limit -> ceiling for the resulting limit, also the limit used for the preflight run (default 150000)
+#[derive(Debug, Clone)]
+pub struct GasEstimateConfig {
+    pub margin: f64,
+    pub min_gas_limit: u64,
+    pub block_gas_limit: u64,
+}
+
+impl Default for GasEstimateConfig {
+    fn default() -> Self {
+        Self { margin: 1.2, min_gas_limit: 500, block_gas_limit: BLOCK_GAS_LIMIT }
+    }
+}
+
+impl GasEstimateConfig {
+    /// Gas limit to request for a transaction that consumed `gas_used` in preflight.
+    pub fn limit_for(&self, gas_used: u64) -> Result<u64, String> {
+        if gas_used > self.block_gas_limit {
+            return Err(format!("preflight consumed {} gas, above the block gas limit of {}", gas_used, self.block_gas_limit));
+        }
+        let padded = (gas_used as f64 * self.margin).ceil() as u64;
+        Ok(padded.max(self.min_gas_limit).min(self.block_gas_limit))
+    }
+}
+
+/// Extract gas consumed from a /local response, with or without preflight.
+///
+/// Fails with the node's error message if the transaction itself failed.
+pub fn gas_from_local_response(res: &Value) -> Result<u64, String> {
+    let body = res.get("preflightResult").unwrap_or(res);
+    let status = body.get("result").and_then(|r| r.get("status")).and_then(|s| s.as_str());
+    if status != Some("success") {
+        let msg = body.get("result").and_then(|r| r.get("error")).and_then(|e| e.get("message")).and_then(|m| m.as_str())
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| res.to_string());
+        return Err(format!("preflight failed: {}", msg));
+    }
+    body.get("gas").and_then(|g| g.as_u64()).ok_or_else(|| format!("no gas in local response: {}", res))
+}
+
+const PREFLIGHT: LocalOptions = LocalOptions { preflight: Some(true), signature_verification: None };
+
+/// Run a `fetch::send`-style command object (pactCode/envData/meta/keyPairs)
+/// through preflight /local at the block gas limit and return the gas consumed.
+pub fn estimate_gas(cmd: &Value, api_host: &str, cfg: &GasEstimateConfig) -> Result<u64, String> {
+    let mut probe = cmd.clone();
+    set_gas_limit(&mut probe, cfg.block_gas_limit)?;
+    let res = fetch::local_with_opts(&probe, api_host, Some(PREFLIGHT));
+    gas_from_local_response(&res)
+}
+
+/// Copy of `cmd` whose `meta.gasLimit` is the estimated limit; `fetch::send` re-signs it.
+pub fn with_estimated_gas(cmd: &Value, api_host: &str, cfg: &GasEstimateConfig) -> Result<Value, String> {
+    let limit = cfg.limit_for(estimate_gas(cmd, api_host, cfg)?)?;
+    let mut adjusted = cmd.clone();
+    set_gas_limit(&mut adjusted, limit)?;
+    Ok(adjusted)
+}
+
+/// Estimate gas, adjust `meta.gasLimit` and send. Estimation errors are returned inline as `{"error": ...}`.
+pub fn send_with_estimated_gas(cmd: &Value, api_host: &str, cfg: &GasEstimateConfig, debug: bool) -> Value {
+    match with_estimated_gas(cmd, api_host, cfg) {
+        Ok(adjusted) => fetch::send(&adjusted, api_host, debug),
+        Err(e) => json!({"error": e}),
+    }
+}
+
+/// Typed counterpart of `with_estimated_gas`: preflight `command` signed by
+/// `key_pairs` and return it with the adjusted gas limit, ready to be signed.
+pub fn estimate_command_gas(command: &Command, key_pairs: &[KeyPair], api_host: &str, cfg: &GasEstimateConfig) -> Result<Command, String> {
+    let mut probe = command.clone();
+    probe.meta.gas_limit = cfg.block_gas_limit;
+    let res = fetch::local_signed(&probe.sign(key_pairs)?.to_value(), api_host, Some(PREFLIGHT));
+    let limit = cfg.limit_for(gas_from_local_response(&res)?)?;
+    let mut adjusted = command.clone();
+    adjusted.meta.gas_limit = limit;
+    Ok(adjusted)
+}
+
+fn set_gas_limit(cmd: &mut Value, limit: u64) -> Result<(), String> {
+    let meta = cmd.get_mut("meta").and_then(|m| m.as_object_mut()).ok_or("command has no meta object")?;
+    meta.insert("gasLimit".to_string(), json!(limit));
+    Ok(())
+}
+
+/// Who pays for gas.
+///
+///  Signer  -> the sender in meta pays; scoped signers get `coin.GAS` (the tools' default)
+///  Account -> a separate account pays, its key signs `coin.GAS`
+///  Station -> a gas station account pays; the first signer is scoped to the
+///             station's `GAS_PAYER` capability (user, gas limit, gas price)
+#[derive(Debug, Clone, Default)]
+pub enum GasPayer {
+    #[default]
+    Signer,
+    Account { account: String, key_pair: KeyPair },
+    Station { account: String, module: String },
+}
+
+fn is_gas_cap(cap: &Value) -> bool {
+    cap.get("name").and_then(|n| n.as_str()) == Some("coin.GAS")
+}
+
+fn clist_of(kp: &Value) -> Vec<Value> {
+    kp.get("clist").and_then(|c| c.as_array()).cloned().unwrap_or_default()
+}
+
+/// Rewrite a `fetch::send`-style command object so that `payer` pays for gas.
+///
+/// An empty or missing clist marks an unscoped signer, which already covers
+/// gas and is left alone. Signers that were only there to sign `coin.GAS` are
+/// dropped when someone else pays.
+pub fn apply_gas_payer(cmd: &Value, payer: &GasPayer) -> Result<Value, String> {
+    let mut cmd = cmd.clone();
+    let meta = cmd.get("meta").and_then(|m| m.as_object()).cloned().ok_or("command has no meta object")?;
+    let mut key_pairs: Vec<Value> = cmd.get("keyPairs").and_then(|k| k.as_array()).cloned().unwrap_or_default();
+
+    let sender = match payer {
+        GasPayer::Signer => {
+            for kp in key_pairs.iter_mut() {
+                let mut clist = clist_of(kp);
+                if !clist.is_empty() && !clist.iter().any(is_gas_cap) {
+                    clist.insert(0, json!(Capability::gas()));
+                    kp["clist"] = json!(clist);
+                }
+            }
+            meta.get("sender").and_then(|s| s.as_str()).unwrap_or("").to_string()
+        }
+        GasPayer::Account { account, key_pair } => {
+            key_pairs = strip_gas_caps(key_pairs);
+            match key_pairs.iter_mut().find(|kp| kp.get("publicKey").and_then(|p| p.as_str()) == Some(key_pair.public_key.as_str())) {
+                Some(kp) => {
+                    let mut clist = clist_of(kp);
+                    if !clist.is_empty() {
+                        clist.insert(0, json!(Capability::gas()));
+                        kp["clist"] = json!(clist);
+                    }
+                }
+                None => key_pairs.push(json!({
+                    "publicKey": key_pair.public_key,
+                    "secretKey": key_pair.secret_key,
+                    "clist": [Capability::gas()]
+                })),
+            }
+            account.clone()
+        }
+        GasPayer::Station { account, module } => {
+            let user = meta.get("sender").and_then(|s| s.as_str()).unwrap_or("").to_string();
+            let gas_limit = meta.get("gasLimit").and_then(|g| g.as_u64()).unwrap_or(0);
+            let gas_price: PactDecimal = match meta.get("gasPrice") {
+                Some(g) => serde_json::from_value(g.clone()).map_err(|e| format!("malformed gasPrice: {}", e))?,
+                None => PactDecimal::zero(),
+            };
+            let carrier = key_pairs.iter().position(|kp| !clist_of(kp).is_empty())
+                .ok_or("a gas station needs a scoped signer to carry its GAS_PAYER capability")?;
+            let mut clist: Vec<Value> = clist_of(&key_pairs[carrier]).into_iter().filter(|c| !is_gas_cap(c)).collect();
+            clist.insert(0, json!(Capability::gas_payer(module, &user, gas_limit, gas_price)));
+            let mut carrier_kp = key_pairs.remove(carrier);
+            carrier_kp["clist"] = json!(clist);
+            key_pairs = strip_gas_caps(key_pairs);
+            key_pairs.insert(carrier.min(key_pairs.len()), carrier_kp);
+            account.clone()
+        }
+    };
+
+    cmd["meta"]["sender"] = json!(sender);
+    cmd["keyPairs"] = json!(key_pairs);
+    Ok(cmd)
+}
+
+// Remove coin.GAS from scoped signers, dropping those that signed for nothing else
+fn strip_gas_caps(key_pairs: Vec<Value>) -> Vec<Value> {
+    key_pairs.into_iter().filter_map(|mut kp| {
+        let clist = clist_of(&kp);
+        if clist.is_empty() {
+            return Some(kp);
+        }
+        let kept: Vec<Value> = clist.into_iter().filter(|c| !is_gas_cap(c)).collect();
+        if kept.is_empty() {
+            return None;
+        }
+        kp["clist"] = json!(kept);
+        Some(kp)
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer() -> Value {
+        json!(Capability::new("coin.TRANSFER", vec![json!("k:a"), json!("k:b"), json!({"decimal": "1.0"})]))
+    }
+
+    fn cmd(key_pairs: Value) -> Value {
+        json!({"pactCode": "1", "meta": {"sender": "k:a", "gasLimit": 1000, "gasPrice": 0.5}, "keyPairs": key_pairs})
+    }
+
+    #[test]
+    fn limits_are_padded_and_clamped() {
+        let cfg = GasEstimateConfig::default();
+        assert_eq!(cfg.limit_for(1000), Ok(1200));
+        assert_eq!(cfg.limit_for(10), Ok(500));
+        assert_eq!(cfg.limit_for(140000), Ok(BLOCK_GAS_LIMIT));
+        assert!(cfg.limit_for(BLOCK_GAS_LIMIT + 1).is_err());
+    }
+
+    #[test]
+    fn gas_is_read_from_local_and_preflight_responses() {
+        assert_eq!(gas_from_local_response(&json!({"result": {"status": "success"}, "gas": 7})), Ok(7));
+        assert_eq!(gas_from_local_response(&json!({"preflightResult": {"result": {"status": "success"}, "gas": 9}})), Ok(9));
+        let failed = json!({"result": {"status": "failure", "error": {"message": "boom"}}, "gas": 9});
+        assert_eq!(gas_from_local_response(&failed), Err("preflight failed: boom".to_string()));
+        assert!(gas_from_local_response(&json!({"result": {"status": "success"}})).unwrap_err().starts_with("no gas"));
+    }
+
+    #[test]
+    fn signer_pays_with_coin_gas_added_to_scoped_signers() {
+        let out = apply_gas_payer(&cmd(json!([{"publicKey": "a", "clist": [transfer()]}, {"publicKey": "u"}])), &GasPayer::Signer).unwrap();
+        assert_eq!(out["keyPairs"], json!([{"publicKey": "a", "clist": [Capability::gas(), transfer()]}, {"publicKey": "u"}]));
+        assert_eq!(out["meta"]["sender"], "k:a");
+        assert_eq!(apply_gas_payer(&out, &GasPayer::Signer).unwrap(), out);
+    }
+
+    #[test]
+    fn another_account_pays() {
+        let payer = GasPayer::Account { account: "k:p".to_string(), key_pair: KeyPair { public_key: "p".to_string(), secret_key: "s".to_string(), ..Default::default() } };
+        let out = apply_gas_payer(&cmd(json!([{"publicKey": "a", "clist": [Capability::gas(), transfer()]}, {"publicKey": "g", "clist": [Capability::gas()]}])), &payer).unwrap();
+        assert_eq!(out["keyPairs"], json!([{"publicKey": "a", "clist": [transfer()]}, {"publicKey": "p", "secretKey": "s", "clist": [Capability::gas()]}]));
+        assert_eq!(out["meta"]["sender"], "k:p");
+    }
+
+    #[test]
+    fn gas_station_scopes_the_first_scoped_signer() {
+        let station = GasPayer::Station { account: "station".to_string(), module: "free.station".to_string() };
+        let out = apply_gas_payer(&cmd(json!([{"publicKey": "u"}, {"publicKey": "a", "clist": [Capability::gas(), transfer()]}])), &station).unwrap();
+        let gas_payer = Capability::gas_payer("free.station", "k:a", 1000, PactDecimal::parse("0.5").unwrap());
+        assert_eq!(out["keyPairs"], json!([{"publicKey": "u"}, {"publicKey": "a", "clist": [gas_payer, transfer()]}]));
+        assert_eq!(out["meta"]["sender"], "station");
+        assert!(apply_gas_payer(&cmd(json!([{"publicKey": "u"}])), &station).is_err());
+        assert_eq!(apply_gas_payer(&json!({"keyPairs": []}), &GasPayer::Signer), Err("command has no meta object".to_string()));
+    }
+}
@@ -1,18 +1,21 @@
 // Lang module: meta, cap, exp construction
 // ...to be implemented...
+use crate::clock::TimePolicy;
+use crate::decimal::PactDecimal;
+use crate::types::PublicMeta;
 use serde_json::json;
 
 pub fn mk_meta(sender: &str, chain_id: &str, gas_price: f64, gas_limit: u64, creation_time: u64, ttl: u64) -> serde_json::Value {
-	json!({
-		"creationTime": creation_time,
-		"ttl": ttl,
-		"gasLimit": gas_limit,
-		"chainId": chain_id,
-		"gasPrice": gas_price,
-		"sender": sender
-	})
+	serde_json::to_value(PublicMeta::new(sender, chain_id, gas_price, gas_limit, creation_time, ttl)).expect("meta serializes to JSON")
+}
+
+/// Meta whose creationTime and ttl come from `policy`; errors if the policy's ttl exceeds its `max_ttl`.
+pub fn mk_meta_with_policy(sender: &str, chain_id: &str, gas_price: f64, gas_limit: u64, policy: &TimePolicy) -> Result<serde_json::Value, String> {
+	policy.validate_ttl(policy.ttl)?;
+	Ok(mk_meta(sender, chain_id, gas_price, gas_limit, policy.creation_time(), policy.ttl))
 }
 
+/// Legacy `{role, description, cap}` shape; signer clists take `types::Capability`.
 pub fn mk_cap(role: &str, description: &str, name: &str, args: Vec<serde_json::Value>) -> serde_json::Value {
 	json!({
 		"role": role,
@@ -24,6 +27,8 @@ pub fn mk_cap(role: &str, description: &str, name: &str, args: Vec<serde_json::V
 	})
 }
 
+/// Unsafe for untrusted input: kwarg names are ignored and strings starting
+/// with `(` or `[` are emitted verbatim. Prefer `expr::PactExpr`.
 pub fn mk_exp(module_and_function: &str, namespace: Option<&str>, kwargs: Vec<(&str, serde_json::Value)>) -> String {
 	let mut string = String::new();
 	if let Some(ns) = namespace {
@@ -34,6 +39,11 @@ pub fn mk_exp(module_and_function: &str, namespace: Option<&str>, kwargs: Vec<(&
 	for (_key, value) in kwargs {
 		let val_str = match value {
 			serde_json::Value::String(ref s) if s.starts_with('(') || s.starts_with('[') => s.clone(),
+			// Pact JSON encodings of decimals and integers become literals
+			serde_json::Value::Object(ref o) if o.len() == 1 && o.contains_key("decimal") => {
+				serde_json::from_value::<PactDecimal>(value.clone()).map(|d| d.to_pact()).unwrap_or_else(|_| value.to_string())
+			}
+			serde_json::Value::Object(ref o) if o.len() == 1 && o.get("int").map(|i| i.is_i64() || i.is_u64()).unwrap_or(false) => o["int"].to_string(),
 			_ => value.to_string(),
 		};
 		string.push_str(&format!(" {}", val_str));
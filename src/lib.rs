@@ -1,14 +1,50 @@
+pub use rust_pact_syntax::{decimal, expr, parser};
+pub use rust_pact_macros::pact;
+pub use chrono;
+pub use serde_json;
+
 pub mod crypto;
 pub mod api;
+pub mod batch;
+pub mod builder;
+pub mod clock;
+pub mod codegen;
+pub mod continuation;
 pub mod lang;
+pub mod lint;
+pub mod nonce;
 pub mod simple;
+pub mod env;
+pub mod explain;
 pub mod fetch;
+pub mod format;
+pub mod gas;
 pub mod utils;
 pub mod tools;
+pub mod types;
+pub mod validate;
+pub mod vanity;
 
 pub use crypto::{gen_key_pair, sign, verify, attach_sig, sign_map, b64_url_encoded_hash, hash_bin, hex_to_bin, bin_to_hex};
-pub use fetch::{send, listen, poll, local, local_with_opts, spv, send_signed, simple_poll_req_from_exec, simple_listen_req_from_exec, LocalOptions, local_with_options};
-pub use tools::{get_api_host, token_transfer, crosschain_transfer, crosschain_complete, crosschain_transfer_full, CrossChainConfig, poll_create_spv};
+pub use fetch::{send, listen, poll, local, local_with_opts, local_signed, spv, send_signed, send_validated, send_idempotent, IdempotentSendConfig, send_refreshing, refresh_command, RefreshSendConfig, simple_poll_req_from_exec, simple_listen_req_from_exec, LocalOptions, local_with_options};
+pub use tools::{get_api_host, try_get_api_host, token_transfer, crosschain_transfer, crosschain_complete, crosschain_transfer_full, CrossChainConfig, poll_create_spv};
+pub use batch::{send_batch, chunk_commands, BatchConfig};
+pub use codegen::{generate_bindings, generate_bindings_from_node, generate_bindings_from_source};
+pub use builder::TransactionBuilder;
+pub use clock::{TimePolicy, TimeSource, SystemClock, FixedClock, default_policy, set_default_policy};
+pub use decimal::{IntoPactDecimal, PactDecimal, PactInteger, COIN_PRECISION};
+pub use env::EnvData;
+pub use expr::{PactExpr, PactDef, DefKind, ToPact};
+pub use lint::{lint, lint_command, LintConfig, LintIssue, LintKind, ModuleInterface};
+pub use parser::{parse, parse_expr, parse_with_comments, Comment, ParseError, Span, Spanned};
+pub use format::{format_source, format_expr, FormatConfig};
+pub use explain::{explain, explain_command, describe_capability};
+pub use gas::{estimate_gas, send_with_estimated_gas, apply_gas_payer, GasEstimateConfig, GasPayer};
+pub use nonce::{NonceStrategy, next_nonce, default_nonce_strategy, set_default_nonce_strategy};
+pub use continuation::{DefpactDriver, PactContinuation, StepOutcome};
+pub use types::{Command, Payload, ExecPayload, ContPayload, PublicMeta, Signer, SignerScheme, Capability, Sig, SignedCommand, UnsignedCommand, Verifier};
+pub use validate::{validate_command, validate_send_body, ValidationConfig, ValidationIssue, IssueKind};
+pub use vanity::{generate_vanity_key_pair, estimate_difficulty, VanityConfig, VanityProgress};
 
 use serde_json::Value;
 use utils::KeyPair;
@@ -17,10 +53,14 @@ use utils::KeyPair;
 pub struct Pact;
 
 impl Pact {
+	/// Start a fluent transaction, see `builder::TransactionBuilder`.
+	pub fn builder() -> TransactionBuilder { TransactionBuilder::new() }
+
 	pub fn prepare_exec_cmd(pact_code: &str, env_data: Value, meta: Value, network_id: Option<String>, nonce: Option<String>, key_pairs: Option<Vec<KeyPair>>) -> Value {
 		api::prepare_exec_cmd(pact_code, env_data, meta, network_id, nonce, key_pairs)
 	}
 
+	#[allow(clippy::too_many_arguments)]
 	pub fn prepare_cont_cmd(pact_id: &str, rollback: bool, step: u64, proof: Option<String>, env_data: Value, meta: Value, network_id: Option<String>, nonce: Option<String>, key_pairs: Option<Vec<KeyPair>>) -> Value {
 		api::prepare_cont_cmd(pact_id, rollback, step, proof, env_data, meta, network_id, nonce, key_pairs)
 	}
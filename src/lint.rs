@@ -0,0 +1,439 @@
+// Lint module: static checks of Pact code before it is sent or deployed
+use crate::expr::PactExpr;
+use crate::parser::{parse, Span, Spanned};
+use crate::types::{Command, Payload, Signer};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fmt;
+
+/// Names a module exports, for checking `module.member` calls.
+///
+/// `name` is the module name as used in code: `coin` or `free.my-module`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleInterface {
+    pub name: String,
+    pub members: Vec<String>,
+}
+
+impl ModuleInterface {
+    pub fn new(name: &str, members: &[&str]) -> Self {
+        Self { name: name.to_string(), members: members.iter().map(|m| m.to_string()).collect() }
+    }
+
+    /// Interfaces of every `module` and `interface` in `src`, qualified by a
+    /// preceding `(namespace ..)` form if there is one.
+    pub fn from_source(src: &str) -> Result<Vec<Self>, String> {
+        let forms = parse(src).map_err(|e| e.to_string())?;
+        let mut namespace: Option<String> = None;
+        let mut interfaces = Vec::new();
+        for form in &forms {
+            let PactExpr::App(head, args) = &form.expr else { continue };
+            match (head.as_ref(), args.first()) {
+                (PactExpr::Symbol(h), Some(ns)) if h == "namespace" => namespace = literal_name(ns),
+                (PactExpr::Symbol(h), Some(PactExpr::Symbol(name))) if h == "module" || h == "interface" => {
+                    let name = match &namespace {
+                        Some(ns) => format!("{}.{}", ns, name),
+                        None => name.clone(),
+                    };
+                    interfaces.push(Self { name, members: args.iter().filter_map(definition_name).collect() });
+                }
+                _ => {}
+            }
+        }
+        Ok(interfaces)
+    }
+
+    /// Interface from the result of `(describe-module "name")`: its data or the
+    /// whole `/local` response, e.g. from `tools::get_contract_code`.
+    pub fn from_describe_module(described: &Value) -> Result<Self, String> {
+        let described = described.pointer("/result/data").unwrap_or(described);
+        let name = described.get("name").and_then(|n| n.as_str()).ok_or("describe-module result has no name")?;
+        let code = described.get("code").and_then(|c| c.as_str()).ok_or("describe-module result has no code")?;
+        let interface = Self::from_source(code)?.into_iter().next().ok_or("describe-module code defines no module")?;
+        Ok(Self { name: name.to_string(), ..interface })
+    }
+
+    pub fn has_member(&self, member: &str) -> bool {
+        self.members.iter().any(|m| m == member)
+    }
+}
+
+/// What the linter checks code against.
+///
+///  interfaces -> modules whose members `module.member` calls must exist in (default none)
+///  env_data   -> env data the code will run with; `read-keyset`/`read-msg`.. names must be in it (default None: unchecked)
+///  signers    -> signers of the command; capabilities the code uses must be in a clist (default None: unchecked)
+///  builtins   -> whether unqualified calls must be Pact builtins or defined in the code (default true)
+#[derive(Debug, Clone)]
+pub struct LintConfig {
+    pub interfaces: Vec<ModuleInterface>,
+    pub env_data: Option<Value>,
+    pub signers: Option<Vec<Signer>>,
+    pub builtins: bool,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self { interfaces: vec![], env_data: None, signers: None, builtins: true }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintKind {
+    Syntax,
+    UnknownFunction,
+    UndefinedMember,
+    MissingEnvData,
+    MissingCapability,
+}
+
+/// One problem found in Pact source, with where it is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintIssue {
+    pub kind: LintKind,
+    pub message: String,
+    pub span: Span,
+}
+
+impl fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.span, self.message)
+    }
+}
+
+/// Lint Pact source: outgoing `pactCode` or a `.pact` file with modules.
+///
+/// Unbalanced or malformed source yields a single `Syntax` issue. Otherwise
+/// every call is checked: unqualified names must be builtins or defined in
+/// `src` (functions, parameters, `let` and `with-read` bindings), qualified
+/// names must be members of the module's interface when it is known (modules
+/// in `src` are known too), and `cfg`'s env data and signers are checked
+/// against what the code reads and which capabilities it uses.
+///
+/// A `(use ..)` of a module whose interface is unknown does not turn the
+/// builtin check off: unknown names are still reported, with a note that
+/// they may come from that module. Add its `ModuleInterface` to `cfg` to
+/// check them properly.
+///
+/// ```text
+/// let issues = lint("(coin.transfr \"a\" \"b\" 1.0)", &LintConfig {
+///     interfaces: vec![ModuleInterface::new("coin", &["transfer", "TRANSFER"])],
+///     ..LintConfig::default()
+/// });
+/// // 1:2: coin has no member transfr
+/// ```
+pub fn lint(src: &str, cfg: &LintConfig) -> Vec<LintIssue> {
+    let forms = match parse(src) {
+        Ok(forms) => forms,
+        Err(e) => return vec![LintIssue { kind: LintKind::Syntax, message: e.message, span: e.span }],
+    };
+    let mut interfaces = cfg.interfaces.clone();
+    interfaces.extend(ModuleInterface::from_source(src).unwrap_or_default());
+
+    let mut local: HashSet<String> = HashSet::new();
+    let mut unknown_uses: Vec<String> = Vec::new();
+    for form in &forms {
+        form.expr.walk(&mut |e| collect_local_names(e, &mut local));
+        form.expr.walk(&mut |e| {
+            if let Some(module) = used_module(e) {
+                match interfaces.iter().find(|i| i.name == module) {
+                    Some(interface) => local.extend(interface.members.iter().cloned()),
+                    None if !unknown_uses.contains(&module) => unknown_uses.push(module),
+                    None => {}
+                }
+            }
+        });
+    }
+
+    let mut linter = Linter { cfg, interfaces: &interfaces, local: &local, unknown_uses: &unknown_uses, issues: vec![] };
+    for form in &forms {
+        linter.visit(form);
+    }
+    linter.issues
+}
+
+/// Lint the code of an exec command against its own env data and signers
+/// (where `cfg` sets none). Continuations have no code and pass.
+pub fn lint_command(command: &Command, cfg: &LintConfig) -> Vec<LintIssue> {
+    let Payload::Exec(exec) = &command.payload else {
+        return vec![];
+    };
+    let cfg = LintConfig {
+        env_data: cfg.env_data.clone().or_else(|| Some(exec.data.clone())),
+        signers: cfg.signers.clone().or_else(|| Some(command.signers.clone())),
+        ..cfg.clone()
+    };
+    lint(&exec.code, &cfg)
+}
+
+struct Linter<'a> {
+    cfg: &'a LintConfig,
+    interfaces: &'a [ModuleInterface],
+    local: &'a HashSet<String>,
+    // modules brought in by `use` whose members are unknown
+    unknown_uses: &'a [String],
+    issues: Vec<LintIssue>,
+}
+
+impl Linter<'_> {
+    fn issue(&mut self, kind: LintKind, span: Span, message: String) {
+        self.issues.push(LintIssue { kind, message, span });
+    }
+
+    // Metadata such as @model holds property expressions, not code, and is skipped
+    fn visit(&mut self, node: &Spanned) {
+        if matches!(node.expr, PactExpr::Meta(..)) {
+            return;
+        }
+        self.check(node);
+        for child in &node.children {
+            self.visit(child);
+        }
+    }
+
+    fn check(&mut self, node: &Spanned) {
+        let PactExpr::App(head, args) = &node.expr else { return };
+        let head_span = node.children[0].span;
+        match head.as_ref() {
+            PactExpr::Symbol(name) => {
+                if self.cfg.builtins && !PACT_BUILTINS.contains(&name.as_str()) && !self.local.contains(name) {
+                    let message = match self.unknown_uses {
+                        [] => format!("unknown function {}", name),
+                        modules => format!("unknown function {} (unless {} defines it; its interface is unknown)", name, modules.join(" or ")),
+                    };
+                    self.issue(LintKind::UnknownFunction, head_span, message);
+                }
+                self.check_env_read(name, args, node);
+                if CAPABILITY_FORMS.contains(&name.as_str()) {
+                    if let Some(cap) = args.first().and_then(capability_name) {
+                        self.check_capability(&cap, node.children[1].span);
+                    }
+                }
+            }
+            PactExpr::Qualified { module, name, namespace } => {
+                let module = match namespace {
+                    Some(ns) => format!("{}.{}", ns, module),
+                    None => module.clone(),
+                };
+                let Some(interface) = self.interfaces.iter().find(|i| i.name == module) else { return };
+                if !interface.has_member(name) {
+                    self.issue(LintKind::UndefinedMember, head_span, format!("{} has no member {}", module, name));
+                }
+                // only modules known to define the capability need it, not every `transfer`
+                if let Some(cap) = fungible_capability(name).filter(|cap| interface.has_member(cap)) {
+                    self.check_capability(&format!("{}.{}", module, cap), node.span);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // (read-keyset "ks") and friends against the configured env data
+    fn check_env_read(&mut self, function: &str, args: &[PactExpr], node: &Spanned) {
+        let Some(data) = &self.cfg.env_data else { return };
+        let Some(PactExpr::String(key)) = args.first() else { return };
+        let span = node.children[1].span;
+        match function {
+            "read-keyset" => match data.get(key) {
+                None => self.issue(LintKind::MissingEnvData, span, format!("env data has no keyset {:?}", key)),
+                Some(v) if v.get("keys").is_none_or(|k| !k.is_array()) => {
+                    self.issue(LintKind::MissingEnvData, span, format!("env data entry {:?} is not a keyset", key))
+                }
+                Some(_) => {}
+            },
+            "read-msg" | "read-decimal" | "read-integer" | "read-string" if data.get(key).is_none() => {
+                self.issue(LintKind::MissingEnvData, span, format!("env data has no entry {:?}", key))
+            }
+            _ => {}
+        }
+    }
+
+    // A capability the code needs: some signer must scope it, unless one is unscoped
+    fn check_capability(&mut self, cap: &str, span: Span) {
+        let Some(signers) = &self.cfg.signers else { return };
+        if !cap.contains('.') {
+            return;
+        }
+        let granted = signers.iter().any(|s| match &s.clist {
+            None => true,
+            Some(clist) => clist.is_empty() || clist.iter().any(|c| c.name == cap),
+        });
+        if !granted {
+            self.issue(LintKind::MissingCapability, span, format!("{} is not in any signer's clist", cap));
+        }
+    }
+}
+
+// Capability a fungible function needs, e.g. transfer -> TRANSFER
+fn fungible_capability(function: &str) -> Option<&'static str> {
+    match function {
+        "transfer" | "transfer-create" => Some("TRANSFER"),
+        "transfer-crosschain" => Some("TRANSFER_XCHAIN"),
+        "rotate" => Some("ROTATE"),
+        _ => None,
+    }
+}
+
+// Forms whose first argument is a capability application
+const CAPABILITY_FORMS: &[&str] = &["with-capability", "install-capability", "compose-capability", "require-capability"];
+
+// `ns.module.NAME` of a capability application such as `(coin.TRANSFER ..)`
+fn capability_name(expr: &PactExpr) -> Option<String> {
+    match expr {
+        PactExpr::App(head, _) => match head.as_ref() {
+            PactExpr::Qualified { namespace: Some(ns), module, name } => Some(format!("{}.{}.{}", ns, module, name)),
+            PactExpr::Qualified { namespace: None, module, name } => Some(format!("{}.{}", module, name)),
+            PactExpr::Symbol(name) => Some(name.clone()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// Names that `src` itself binds: definitions, parameters, let, lambda and with-read bindings, defconsts
+fn collect_local_names(expr: &PactExpr, names: &mut HashSet<String>) {
+    let strip = |n: &str| n.split(':').next().unwrap_or(n).to_string();
+    match expr {
+        PactExpr::Def(def) => {
+            names.insert(def.name.clone());
+            for (param, _) in def.params.iter().flatten() {
+                names.insert(param.clone());
+            }
+        }
+        PactExpr::Let(bindings, _) | PactExpr::LetStar(bindings, _) => names.extend(bindings.iter().map(|(n, _)| strip(n))),
+        PactExpr::Bindings(fields) => names.extend(fields.iter().filter_map(|(_, v)| member_name(v))),
+        // `(lambda (x y) ..)`: the parameter list parses as the application `(x y)`
+        PactExpr::App(head, args) if matches!(head.as_ref(), PactExpr::Symbol(h) if h == "lambda") => {
+            if let Some(PactExpr::App(first, rest)) = args.first() {
+                names.extend(std::iter::once(first.as_ref()).chain(rest).filter_map(member_name));
+            }
+        }
+        PactExpr::App(head, args) => {
+            if let (PactExpr::Symbol(h), Some(name)) = (head.as_ref(), args.first().and_then(member_name)) {
+                if h == "defconst" || h == "defproperty" || h == "module" || h == "interface" {
+                    names.insert(name);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+// Name bound by a symbol or typed name
+fn member_name(expr: &PactExpr) -> Option<String> {
+    match expr {
+        PactExpr::Symbol(s) => Some(s.clone()),
+        PactExpr::Typed { name, .. } => Some(name.clone()),
+        _ => None,
+    }
+}
+
+// Name of a module member: a definition or defconst
+fn definition_name(expr: &PactExpr) -> Option<String> {
+    match expr {
+        PactExpr::Def(def) => Some(def.name.clone()),
+        PactExpr::App(head, args) if matches!(head.as_ref(), PactExpr::Symbol(h) if h == "defconst") => args.first().and_then(member_name),
+        _ => None,
+    }
+}
+
+// Module named by a `(use ..)` form
+fn used_module(expr: &PactExpr) -> Option<String> {
+    let PactExpr::App(head, args) = expr else { return None };
+    match (head.as_ref(), args.first()) {
+        (PactExpr::Symbol(h), Some(PactExpr::Qualified { namespace: None, module, name })) if h == "use" => Some(format!("{}.{}", module, name)),
+        (PactExpr::Symbol(h), Some(m)) if h == "use" => literal_name(m),
+        _ => None,
+    }
+}
+
+// 'free, "free" or free
+fn literal_name(expr: &PactExpr) -> Option<String> {
+    match expr {
+        PactExpr::String(s) => Some(s.clone()),
+        PactExpr::Symbol(s) => Some(s.trim_start_matches('\'').to_string()),
+        _ => None,
+    }
+}
+
+/// Pact builtin functions and special forms.
+pub const PACT_BUILTINS: &[&str] = &[
+    // special forms
+    "module", "interface", "use", "implements", "bless", "namespace", "defconst", "defproperty",
+    "step", "step-with-rollback", "let", "let*", "cond", "lambda",
+    // operators
+    "+", "-", "*", "/", "^", "<", "<=", "=", ">", ">=", "!=", "and", "or", "not", "and?", "or?", "not?",
+    "&", "|", "~", "xor", "shift", "abs", "ceiling", "floor", "round", "exp", "ln", "log", "mod", "sqrt", "dec",
+    // general
+    "at", "base64-decode", "base64-encode", "bind", "chain-data", "compose", "concat", "constantly", "contains",
+    "continue", "define-namespace", "describe-namespace", "distinct", "drop", "enforce", "enforce-one",
+    "enforce-pact-version", "enumerate", "filter", "fold", "format", "hash", "identity", "if", "int-to-str",
+    "is-charset", "length", "list", "list-modules", "make-list", "map", "pact-id", "pact-version", "read-decimal",
+    "read-integer", "read-keyset", "read-msg", "read-string", "remove", "resume", "reverse", "sort", "str-to-int",
+    "str-to-list", "take", "try", "tx-hash", "typeof", "where", "yield", "zip", "create-principal", "is-principal",
+    "typeof-principal", "validate-principal", "hash-keccak256", "poseidon-hash-hack-a-chain", "verify-spv",
+    "enforce-verifier", "print",
+    // time
+    "add-time", "days", "diff-time", "format-time", "hours", "minutes", "parse-time", "time",
+    // database
+    "create-table", "describe-keyset", "describe-module", "describe-table", "fold-db", "insert", "keylog", "keys",
+    "read", "select", "txids", "txlog", "update", "with-default-read", "with-read", "write",
+    // capabilities and guards
+    "compose-capability", "create-capability-guard", "create-capability-pact-guard", "create-module-guard",
+    "create-pact-guard", "create-user-guard", "emit-event", "enforce-guard", "enforce-keyset", "install-capability",
+    "keyset-ref-guard", "require-capability", "with-capability",
+    // keysets
+    "define-keyset", "keys-2", "keys-all", "keys-any",
+    // zk
+    "pairing-check", "point-add", "scalar-mult",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Capability;
+
+    fn messages(src: &str, cfg: &LintConfig) -> Vec<String> {
+        lint(src, cfg).iter().map(|i| i.to_string()).collect()
+    }
+
+    #[test]
+    fn unknown_use_keeps_builtin_checks() {
+        let issues = messages("(use free.unknown) (enforce true \"x\") (helper 1) (enforce-kesyet \"ks\")", &LintConfig::default());
+        assert_eq!(issues, [
+            "1:40: unknown function helper (unless free.unknown defines it; its interface is unknown)",
+            "1:51: unknown function enforce-kesyet (unless free.unknown defines it; its interface is unknown)",
+        ]);
+        let known = LintConfig { interfaces: vec![ModuleInterface::new("free.known", &["helper"])], ..LintConfig::default() };
+        assert_eq!(messages("(use free.known) (helper 1) (other 2)", &known), ["1:30: unknown function other"]);
+    }
+
+    #[test]
+    fn transfer_needs_the_capability_only_when_the_module_has_it() {
+        let signer = Signer::new("k", Some(vec![Capability::new("coin.GAS", vec![])]));
+        let cfg = LintConfig {
+            interfaces: vec![ModuleInterface::new("coin", &["transfer", "TRANSFER"]), ModuleInterface::new("free.dex", &["transfer"])],
+            signers: Some(vec![signer]),
+            ..LintConfig::default()
+        };
+        assert_eq!(messages("(coin.transfer \"a\" \"b\" 1.0)", &cfg), ["1:1: coin.TRANSFER is not in any signer's clist"]);
+        assert!(messages("(free.dex.transfer \"a\" \"b\" 1.0)", &cfg).is_empty());
+        assert!(messages("(free.unknown.transfer \"a\" \"b\" 1.0)", &cfg).is_empty());
+    }
+
+    #[test]
+    fn capability_forms_name_the_capability() {
+        let cfg = LintConfig { signers: Some(vec![Signer::new("k", Some(vec![Capability::gas()]))]), ..LintConfig::default() };
+        assert_eq!(messages("(with-capability (free.m.CAP 1) 1)", &cfg), ["1:18: free.m.CAP is not in any signer's clist"]);
+        assert!(messages("(with-capability (coin.GAS) 1)", &cfg).is_empty());
+        let issues = lint("(with-capability (a' 1) 1)", &cfg);
+        assert!(issues.len() == 1 && issues[0].kind == LintKind::Syntax, "{:?}", issues);
+    }
+
+    #[test]
+    fn lambda_parameters_are_bound() {
+        assert!(messages("(map (lambda (x) (+ x 1)) [1 2])", &LintConfig::default()).is_empty());
+        assert!(messages("(fold (lambda (acc:integer x) (+ acc x)) 0 [1 2])", &LintConfig::default()).is_empty());
+        assert_eq!(messages("(map (lambda (x) (y x)) [1])", &LintConfig::default()), ["1:19: unknown function y"]);
+    }
+}
@@ -0,0 +1,129 @@
+// Nonce module: nonce strategies that keep request keys unique within a process
+use chrono::{DateTime, Utc};
+use std::fmt;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+/// How the nonce of a new command is chosen.
+///
+///  Timestamp        -> RFC3339 timestamp, bumped by a nanosecond when another
+///                      thread already used the same instant (default)
+///  TimestampCounter -> RFC3339 timestamp followed by `-<counter>`
+///  Random           -> 128 random bits in hex followed by `-<counter>`
+///  Custom           -> nonces produced by the caller's function, used as is
+///
+/// Except for `Custom`, nonces handed out by one process never repeat, across
+/// all threads and all strategies sharing the process-wide counters. Two
+/// otherwise identical commands therefore get different request keys.
+#[derive(Clone, Default)]
+pub enum NonceStrategy {
+    #[default]
+    Timestamp,
+    TimestampCounter,
+    Random,
+    Custom(Arc<dyn Fn() -> String + Send + Sync>),
+}
+
+impl fmt::Debug for NonceStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NonceStrategy::Timestamp => f.write_str("Timestamp"),
+            NonceStrategy::TimestampCounter => f.write_str("TimestampCounter"),
+            NonceStrategy::Random => f.write_str("Random"),
+            NonceStrategy::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+static LAST_TIMESTAMP_NANOS: AtomicI64 = AtomicI64::new(0);
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+impl NonceStrategy {
+    /// A strategy backed by `f`, e.g. nonces from an external sequence.
+    pub fn custom(f: impl Fn() -> String + Send + Sync + 'static) -> Self {
+        NonceStrategy::Custom(Arc::new(f))
+    }
+
+    pub fn next(&self) -> String {
+        match self {
+            NonceStrategy::Timestamp => unique_timestamp().to_rfc3339(),
+            NonceStrategy::TimestampCounter => format!("{}-{}", Utc::now().to_rfc3339(), next_count()),
+            NonceStrategy::Random => {
+                use rand::RngCore;
+                let mut bytes = [0u8; 16];
+                rand::thread_rng().fill_bytes(&mut bytes);
+                format!("{}-{}", hex::encode(bytes), next_count())
+            }
+            NonceStrategy::Custom(f) => f(),
+        }
+    }
+}
+
+fn next_count() -> u64 {
+    COUNTER.fetch_add(1, Ordering::Relaxed)
+}
+
+// Current time, or one nanosecond past the last instant handed out if that is not earlier
+fn unique_timestamp() -> DateTime<Utc> {
+    let now = Utc::now().timestamp_nanos_opt().unwrap_or(i64::MAX);
+    let prev = LAST_TIMESTAMP_NANOS
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |last| Some(now.max(last + 1)))
+        .expect("update closure always returns Some");
+    DateTime::from_timestamp_nanos(now.max(prev + 1))
+}
+
+static DEFAULT_STRATEGY: RwLock<Option<NonceStrategy>> = RwLock::new(None);
+
+/// Strategy used where no nonce is given explicitly.
+pub fn default_nonce_strategy() -> NonceStrategy {
+    DEFAULT_STRATEGY.read().unwrap_or_else(|e| e.into_inner()).clone().unwrap_or_default()
+}
+
+/// Replace the process-wide default strategy.
+pub fn set_default_nonce_strategy(strategy: NonceStrategy) {
+    *DEFAULT_STRATEGY.write().unwrap_or_else(|e| e.into_inner()) = Some(strategy);
+}
+
+/// Next nonce from the default strategy.
+pub fn next_nonce() -> String {
+    default_nonce_strategy().next()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn nonces_from_threads(strategy: &NonceStrategy) -> Vec<String> {
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..8).map(|_| scope.spawn(|| (0..500).map(|_| strategy.next()).collect::<Vec<_>>())).collect();
+            workers.into_iter().flat_map(|w| w.join().unwrap()).collect()
+        })
+    }
+
+    #[test]
+    fn nonces_are_unique_across_threads() {
+        for strategy in [NonceStrategy::Timestamp, NonceStrategy::TimestampCounter, NonceStrategy::Random] {
+            let nonces = nonces_from_threads(&strategy);
+            assert_eq!(nonces.iter().collect::<HashSet<_>>().len(), nonces.len(), "{:?}", strategy);
+        }
+    }
+
+    #[test]
+    fn timestamps_increase_and_parse() {
+        let stamps: Vec<DateTime<Utc>> = (0..100).map(|_| NonceStrategy::Timestamp.next().parse().unwrap()).collect();
+        assert!(stamps.windows(2).all(|w| w[0] < w[1]));
+        let counted = NonceStrategy::TimestampCounter.next();
+        let (stamp, count) = counted.rsplit_once('-').unwrap();
+        assert!(stamp.parse::<DateTime<Utc>>().is_ok() && count.parse::<u64>().is_ok(), "{}", counted);
+        let random = NonceStrategy::Random.next();
+        assert_eq!(random.split_once('-').map(|(hex, _)| hex.len()), Some(32), "{}", random);
+    }
+
+    #[test]
+    fn custom_nonces_are_used_as_is() {
+        let strategy = NonceStrategy::custom(|| "mine".to_string());
+        assert_eq!((strategy.next(), strategy.next()), ("mine".to_string(), "mine".to_string()));
+        assert_eq!(format!("{:?}", strategy), "Custom(..)");
+    }
+}
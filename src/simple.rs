@@ -16,10 +16,12 @@ pub mod exec {
 
 pub mod cont {
     use super::*;
+    #[allow(clippy::too_many_arguments)]
     pub fn prepare_cont_cmd(pact_id: &str, rollback: bool, step: u64, proof: Option<String>, env_data: Value, meta: Value, network_id: Option<String>, nonce: Option<String>, key_pairs: Option<Vec<KeyPair>>) -> Value {
         crate::api::prepare_cont_cmd(pact_id, rollback, step, proof, env_data, meta, network_id, nonce, key_pairs)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn simple_cont_command(pact_id: &str, rollback: bool, step: u64, proof: Option<String>, env_data: Value, meta: Value, network_id: Option<String>, nonce: Option<String>, key_pairs: Option<Vec<KeyPair>>) -> Value {
         let cmd = prepare_cont_cmd(pact_id, rollback, step, proof, env_data, meta, network_id, nonce, key_pairs);
         mk_public_send(vec![cmd])
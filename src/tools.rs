@@ -1,7 +1,17 @@
 use crate::{lang, fetch};
 use serde_json::{json, Value};
 use crate::utils::KeyPair;
-use std::time::{SystemTime, UNIX_EPOCH};
+use crate::clock::{default_policy, TimePolicy};
+use crate::gas::{apply_gas_payer, GasPayer};
+use crate::nonce::next_nonce;
+use crate::env::EnvData;
+use crate::types::Capability;
+use crate::expr::PactExpr;
+use crate::fetch::RefreshSendConfig;
+use crate::decimal::{IntoPactDecimal, PactDecimal, COIN_PRECISION};
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+use std::time::SystemTime;
 use std::thread::sleep;
 use std::time::Duration;
 
@@ -19,6 +29,10 @@ use std::time::Duration;
 ///  XCHAIN_FINAL_INTERVAL_MS   -> interval ms between final polls (default 5000)
 ///  XCHAIN_VERBOSE             -> if set (any value), emit diagnostic prints
 ///  XCHAIN_MAX_TOTAL_TIME_MS   -> max total time for all polling combined (default 360000 = 6min, 0 = no limit)
+///
+/// `time_policy` sets creationTime/ttl of the commands submitted along the way
+/// (default `clock::default_policy()`, see `TimePolicy` for its env vars), and
+/// `gas_payer` who pays for them on both chains (default: the signer).
 #[derive(Debug, Clone)]
 pub struct CrossChainConfig {
     pub attempts_tx: u32,
@@ -30,6 +44,8 @@ pub struct CrossChainConfig {
     pub interval_final_ms: u64,
     pub verbose: bool,
     pub max_total_time_ms: u64,
+    pub time_policy: TimePolicy,
+    pub gas_payer: GasPayer,
 }
 
 impl Default for CrossChainConfig {
@@ -44,23 +60,29 @@ impl Default for CrossChainConfig {
         let interval_final_ms = env::var("XCHAIN_FINAL_INTERVAL_MS").ok().and_then(|v| v.parse().ok()).unwrap_or(5000);
         let verbose = env::var("XCHAIN_VERBOSE").is_ok();
         let max_total_time_ms = env::var("XCHAIN_MAX_TOTAL_TIME_MS").ok().and_then(|v| v.parse().ok()).unwrap_or(360000); // 6 minutes default to ensure full 5min wait
-        Self { attempts_tx, interval_tx_ms, post_confirm_wait_ms, attempts_spv, interval_spv_ms, attempts_final, interval_final_ms, verbose, max_total_time_ms }
+        let time_policy = default_policy();
+        Self { attempts_tx, interval_tx_ms, post_confirm_wait_ms, attempts_spv, interval_spv_ms, attempts_final, interval_final_ms, verbose, max_total_time_ms, time_policy, gas_payer: GasPayer::Signer }
     }
 }
 
 pub fn get_api_host(network_id: &str, chain_id: &str) -> String {
+    try_get_api_host(network_id, chain_id).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// `get_api_host` returning an error instead of panicking on unknown networks.
+pub fn try_get_api_host(network_id: &str, chain_id: &str) -> Result<String, String> {
     match network_id {
         // Correct mapping: networkId must match chainweb path segment
-        "mainnet01" => format!("https://api.chainweb.com/chainweb/0.0/mainnet01/chain/{}/pact", chain_id),
-        "testnet04" => format!("https://api.testnet.chainweb.com/chainweb/0.0/testnet04/chain/{}/pact", chain_id),
-        other => panic!("Unsupported network_id: {}", other)
+        "mainnet01" => Ok(format!("https://api.chainweb.com/chainweb/0.0/mainnet01/chain/{}/pact", chain_id)),
+        "testnet04" => Ok(format!("https://api.testnet.chainweb.com/chainweb/0.0/testnet04/chain/{}/pact", chain_id)),
+        other => Err(format!("Unsupported network_id: {}", other))
     }
 }
 
 /// Retrieve SPV proof for cross-chain transfer, matching JavaScript reference pattern
 /// Simplified polling loop that directly queries SPV without pre-checking transaction status
 pub fn poll_create_spv(request_key: &str, source_chain_id: &str, target_chain_id: &str, network_id: &str, max_attempts: Option<u32>, interval_ms: u64) -> Result<String, String> {
-    let api_host = get_api_host(network_id, source_chain_id); // SPV retrieved from source chain
+    let api_host = try_get_api_host(network_id, source_chain_id)?; // SPV retrieved from source chain
     let verbose = std::env::var("XCHAIN_VERBOSE").is_ok();
     let spv_cmd = json!({"requestKey": request_key, "targetChainId": target_chain_id});
     let max_attempts = max_attempts.unwrap_or(0); // 0 = infinite
@@ -122,49 +144,116 @@ pub fn poll_create_spv(request_key: &str, source_chain_id: &str, target_chain_id
     }
 }
 
+// Env data with the receiver's guard as keyset "ks"
+fn receiver_env(receiver_public_key: &str) -> EnvData {
+    EnvData::new().add_keyset("ks", "keys-all", &[receiver_public_key])
+}
+
+// Amounts may not carry more decimal places than the token supports
+fn checked_amount(token_address: &str, amount: impl IntoPactDecimal, chain_id: &str, network_id: &str) -> Result<PactDecimal, String> {
+    let amount = amount.into_pact_decimal()?;
+    amount.check_precision(token_precision(token_address, chain_id, network_id)?)?;
+    Ok(amount)
+}
+
+// Token precisions keyed by (network, chain, token), filled by lookups and `set_token_precision`
+static TOKEN_PRECISIONS: RwLock<BTreeMap<(String, String, String), u32>> = RwLock::new(BTreeMap::new());
+
+/// Record the precision of `token_address` on a chain so transfers of it
+/// skip the `/local` lookup in `token_precision`.
+pub fn set_token_precision(token_address: &str, chain_id: &str, network_id: &str, precision: u32) {
+    let key = (network_id.to_string(), chain_id.to_string(), token_address.to_string());
+    TOKEN_PRECISIONS.write().unwrap_or_else(|e| e.into_inner()).insert(key, precision);
+}
+
+/// Decimal places a fungible-v2 token supports: `COIN_PRECISION` for `coin`,
+/// otherwise the value recorded with `set_token_precision`, or else the result
+/// of the token's `(precision)` function on the chain, which is then cached.
+pub fn token_precision(token_address: &str, chain_id: &str, network_id: &str) -> Result<u32, String> {
+    if token_address == "coin" {
+        return Ok(COIN_PRECISION);
+    }
+    let key = (network_id.to_string(), chain_id.to_string(), token_address.to_string());
+    if let Some(precision) = TOKEN_PRECISIONS.read().unwrap_or_else(|e| e.into_inner()).get(&key) {
+        return Ok(*precision);
+    }
+    let code = PactExpr::call(&format!("{}.precision", token_address), vec![])
+        .map_err(|e| format!("invalid token address {:?}: {}", token_address, e))?;
+    let res = local_code(&code.to_string(), chain_id, network_id);
+    if let Some(e) = res.get("error") {
+        return Err(format!("cannot read the precision of {}: {}", token_address, e));
+    }
+    if res.pointer("/result/status").and_then(|s| s.as_str()) != Some("success") {
+        return Err(format!("cannot read the precision of {}: {}", token_address, res.pointer("/result/error").unwrap_or(&res)));
+    }
+    let precision = res.pointer("/result/data")
+        .and_then(|d| d.as_u64().or_else(|| d.get("int").and_then(|i| i.as_u64())))
+        .and_then(|p| u32::try_from(p).ok())
+        .ok_or_else(|| format!("cannot read the precision of {}: unexpected result {}", token_address, res["result"]["data"]))?;
+    set_token_precision(token_address, chain_id, network_id, precision);
+    Ok(precision)
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn token_transfer(token_address: &str,
                       sender_account: &str,
                       receiver_account: &str,
                       receiver_public_key: &str,
-                      amount: f64,
-                      mut key_pair: KeyPair,
+                      amount: impl IntoPactDecimal,
+                      key_pair: KeyPair,
                       chain_id: &str,
                       network_id: &str) -> Value {
-    let api_host = get_api_host(network_id, chain_id);
-
-    let code = if token_address != "coin" {
-        let parts: Vec<&str> = token_address.split('.').collect();
-        if parts.len() != 2 { panic!("token address must be namespace.module"); }
-        lang::mk_exp(&format!("{}.transfer-create", parts[1]), Some(parts[0]), vec![
-            ("sender_account", json!(sender_account)),
-            ("receiver_account", json!(receiver_account)),
-            ("keyset", json!("(read-keyset \"ks\")")),
-            ("amount", json!(amount))
-        ])
-    } else {
-        lang::mk_exp("coin.transfer-create", None, vec![
-            ("sender_account", json!(sender_account)),
-            ("receiver_account", json!(receiver_account)),
-            ("keyset", json!("(read-keyset \"ks\")")),
-            ("amount", json!(amount))
-        ])
+    token_transfer_with_gas_payer(token_address, sender_account, receiver_account, receiver_public_key, amount, key_pair, chain_id, network_id, &GasPayer::Signer)
+}
+
+/// `token_transfer` with gas paid by `gas_payer` instead of the signer's `k:` account.
+#[allow(clippy::too_many_arguments)]
+pub fn token_transfer_with_gas_payer(token_address: &str,
+                                     sender_account: &str,
+                                     receiver_account: &str,
+                                     receiver_public_key: &str,
+                                     amount: impl IntoPactDecimal,
+                                     mut key_pair: KeyPair,
+                                     chain_id: &str,
+                                     network_id: &str,
+                                     gas_payer: &GasPayer) -> Value {
+    let api_host = match try_get_api_host(network_id, chain_id) {
+        Ok(host) => host,
+        Err(e) => return json!({"error": e}),
+    };
+    let amount = match checked_amount(token_address, amount, chain_id, network_id) {
+        Ok(a) => a,
+        Err(e) => return json!({"error": e}),
+    };
+    let env = receiver_env(receiver_public_key);
+
+    let code = match PactExpr::call(&format!("{}.transfer-create", token_address), vec![
+        PactExpr::string(sender_account),
+        PactExpr::string(receiver_account),
+        env.read_expr("ks").expect("ks is a keyset"),
+        PactExpr::decimal(&amount),
+    ]) {
+        Ok(code) => code.to_string(),
+        Err(e) => return json!({"error": format!("invalid token address {:?}: {}", token_address, e)}),
     };
 
     // Add capabilities (GAS + TRANSFER)
     key_pair.clist = Some(vec![
-        json!({"name": "coin.GAS", "args": []}),
-        json!({"name": format!("{}.TRANSFER", token_address), "args": [sender_account, receiver_account, amount]})
+        Capability::gas(),
+        Capability::fungible_transfer(token_address, sender_account, receiver_account, &amount),
     ]);
 
-    let creation_time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64 - 100;
-    let meta = lang::mk_meta(&format!("k:{}", key_pair.public_key), chain_id, 0.0000001, 60000, creation_time as u64, 15000);
+    let meta = match lang::mk_meta_with_policy(&format!("k:{}", key_pair.public_key), chain_id, 0.0000001, 60000, &default_policy()) {
+        Ok(meta) => meta,
+        Err(e) => return json!({"error": e}),
+    };
 
     let cmd = json!({
         "pactCode": code,
-        "envData": {"ks": {"pred": "keys-all", "keys": [receiver_public_key]}},
+        "envData": env.to_value(),
         "meta": meta,
         "networkId": network_id,
-        "nonce": chrono::Utc::now().to_rfc3339(),
+        "nonce": next_nonce(),
         "keyPairs": [json!({
             "publicKey": key_pair.public_key,
             "secretKey": key_pair.secret_key,
@@ -172,39 +261,74 @@ pub fn token_transfer(token_address: &str,
         })]
     });
 
-    fetch::send(&cmd, &api_host, false)
+    match apply_gas_payer(&cmd, gas_payer) {
+        Ok(cmd) => fetch::send(&cmd, &api_host, false),
+        Err(e) => json!({"error": e}),
+    }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn crosschain_transfer(token_address: &str,
                           sender_account: &str,
                           receiver_account: &str,
                           receiver_public_key: &str,
-                          amount: f64,
-                          mut key_pair: KeyPair,
+                          amount: impl IntoPactDecimal,
+                          key_pair: KeyPair,
                           source_chain_id: &str,
                           target_chain_id: &str,
                           network_id: &str,
                                                 x_chain_v1: Option<bool>) -> Value {
-    let api_host = get_api_host(network_id, source_chain_id);
-
-    let code = if token_address != "coin" {
-        let parts: Vec<&str> = token_address.split('.').collect();
-        if parts.len() != 2 { panic!("token address must be namespace.module"); }
-        lang::mk_exp(&format!("{}.transfer-crosschain", parts[1]), Some(parts[0]), vec![
-            ("sender_account", json!(sender_account)),
-            ("receiver_account", json!(receiver_account)),
-            ("receiver_guard", json!("(read-keyset \"ks\")")),
-            ("target_chain", json!(target_chain_id)),
-            ("amount", json!(amount))
-        ])
-    } else {
-        lang::mk_exp("coin.transfer-crosschain", None, vec![
-            ("sender_account", json!(sender_account)),
-            ("receiver_account", json!(receiver_account)),
-            ("receiver_guard", json!("(read-keyset \"ks\")")),
-            ("target_chain", json!(target_chain_id)),
-            ("amount", json!(amount))
-        ])
+    crosschain_transfer_with_policy(token_address, sender_account, receiver_account, receiver_public_key, amount, key_pair, source_chain_id, target_chain_id, network_id, x_chain_v1, &default_policy(), &GasPayer::Signer)
+}
+
+/// `crosschain_transfer` with gas on the source chain paid by `gas_payer`.
+#[allow(clippy::too_many_arguments)]
+pub fn crosschain_transfer_with_gas_payer(token_address: &str,
+                                          sender_account: &str,
+                                          receiver_account: &str,
+                                          receiver_public_key: &str,
+                                          amount: impl IntoPactDecimal,
+                                          key_pair: KeyPair,
+                                          source_chain_id: &str,
+                                          target_chain_id: &str,
+                                          network_id: &str,
+                                          x_chain_v1: Option<bool>,
+                                          gas_payer: &GasPayer) -> Value {
+    crosschain_transfer_with_policy(token_address, sender_account, receiver_account, receiver_public_key, amount, key_pair, source_chain_id, target_chain_id, network_id, x_chain_v1, &default_policy(), gas_payer)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn crosschain_transfer_with_policy(token_address: &str,
+                                   sender_account: &str,
+                                   receiver_account: &str,
+                                   receiver_public_key: &str,
+                                   amount: impl IntoPactDecimal,
+                                   mut key_pair: KeyPair,
+                                   source_chain_id: &str,
+                                   target_chain_id: &str,
+                                   network_id: &str,
+                                   x_chain_v1: Option<bool>,
+                                   policy: &TimePolicy,
+                                   gas_payer: &GasPayer) -> Value {
+    let api_host = match try_get_api_host(network_id, source_chain_id) {
+        Ok(host) => host,
+        Err(e) => return json!({"error": e}),
+    };
+    let amount = match checked_amount(token_address, amount, source_chain_id, network_id) {
+        Ok(a) => a,
+        Err(e) => return json!({"error": e}),
+    };
+    let env = receiver_env(receiver_public_key);
+
+    let code = match PactExpr::call(&format!("{}.transfer-crosschain", token_address), vec![
+        PactExpr::string(sender_account),
+        PactExpr::string(receiver_account),
+        env.read_expr("ks").expect("ks is a keyset"),
+        PactExpr::string(target_chain_id),
+        PactExpr::decimal(&amount),
+    ]) {
+        Ok(code) => code.to_string(),
+        Err(e) => return json!({"error": format!("invalid token address {:?}: {}", token_address, e)}),
     };
 
     // Normalize flag to default false when not provided
@@ -213,23 +337,25 @@ pub fn crosschain_transfer(token_address: &str,
     // Add capabilities (GAS + TRANSFER_XCHAIN) only when x_chain_v1 is enabled; otherwise, do not include these caps
     if x_chain_v1 {
         key_pair.clist = Some(vec![
-            json!({"name": "coin.GAS", "args": []}),
-            json!({"name": format!("{}.TRANSFER_XCHAIN", token_address), "args": [sender_account, receiver_account, amount, target_chain_id]})
+            Capability::gas(),
+            Capability::fungible_transfer_xchain(token_address, sender_account, receiver_account, &amount, target_chain_id),
         ]);
     } else {
         // Ensure we don't accidentally reuse caps from a previous call; set empty clist
         key_pair.clist = Some(vec![]);
     }
 
-    let creation_time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64 - 100;
-    let meta = lang::mk_meta(&format!("k:{}", key_pair.public_key), source_chain_id, 0.0000001, 60000, creation_time as u64, 15000);
+    let meta = match lang::mk_meta_with_policy(&format!("k:{}", key_pair.public_key), source_chain_id, 0.0000001, 60000, policy) {
+        Ok(meta) => meta,
+        Err(e) => return json!({"error": e}),
+    };
 
     let cmd = json!({
         "pactCode": code,
-        "envData": {"ks": {"pred": "keys-all", "keys": [receiver_public_key]}},
+        "envData": env.to_value(),
         "meta": meta,
         "networkId": network_id,
-        "nonce": chrono::Utc::now().to_rfc3339(),
+        "nonce": next_nonce(),
         "keyPairs": [json!({
             "publicKey": key_pair.public_key,
             "secretKey": key_pair.secret_key,
@@ -237,26 +363,72 @@ pub fn crosschain_transfer(token_address: &str,
         })]
     });
 
-    fetch::send(&cmd, &api_host, false)
+    match apply_gas_payer(&cmd, gas_payer) {
+        Ok(cmd) => fetch::send(&cmd, &api_host, false),
+        Err(e) => json!({"error": e}),
+    }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn crosschain_complete(pact_id: &str,
                           proof: &str,
                           _receiver_account: &str,
                           receiver_public_key: &str,
-                          _amount: f64,
-                          mut key_pair: KeyPair,
+                          _amount: impl IntoPactDecimal,
+                          key_pair: KeyPair,
                           target_chain_id: &str,
                           network_id: &str) -> Value {
-    let api_host = get_api_host(network_id, target_chain_id);
+    crosschain_complete_with_policy(pact_id, proof, key_pair, receiver_public_key, target_chain_id, network_id, &default_policy(), &GasPayer::Signer)
+}
+
+/// `crosschain_complete` with gas on the target chain paid by `gas_payer`,
+/// e.g. a gas station when the receiver has no funds there yet.
+#[allow(clippy::too_many_arguments)]
+pub fn crosschain_complete_with_gas_payer(pact_id: &str,
+                                          proof: &str,
+                                          _receiver_account: &str,
+                                          receiver_public_key: &str,
+                                          _amount: impl IntoPactDecimal,
+                                          key_pair: KeyPair,
+                                          target_chain_id: &str,
+                                          network_id: &str,
+                                          gas_payer: &GasPayer) -> Value {
+    crosschain_complete_with_policy(pact_id, proof, key_pair, receiver_public_key, target_chain_id, network_id, &default_policy(), gas_payer)
+}
 
+#[allow(clippy::too_many_arguments)]
+fn crosschain_complete_with_policy(pact_id: &str,
+                                   proof: &str,
+                                   key_pair: KeyPair,
+                                   receiver_public_key: &str,
+                                   target_chain_id: &str,
+                                   network_id: &str,
+                                   policy: &TimePolicy,
+                                   gas_payer: &GasPayer) -> Value {
+    let api_host = match try_get_api_host(network_id, target_chain_id) {
+        Ok(host) => host,
+        Err(e) => return json!({"error": e}),
+    };
+    match crosschain_complete_cmd(pact_id, proof, key_pair, receiver_public_key, target_chain_id, network_id, policy, gas_payer) {
+        Ok(cmd) => fetch::send(&cmd, &api_host, false),
+        Err(e) => json!({"error": e}),
+    }
+}
+
+// Continuation command object for `crosschain_complete`, ready for `fetch::send`
+#[allow(clippy::too_many_arguments)]
+fn crosschain_complete_cmd(pact_id: &str,
+                           proof: &str,
+                           mut key_pair: KeyPair,
+                           receiver_public_key: &str,
+                           target_chain_id: &str,
+                           network_id: &str,
+                           policy: &TimePolicy,
+                           gas_payer: &GasPayer) -> Result<Value, String> {
     // Add capabilities for completing crosschain transfer
-    key_pair.clist = Some(vec![
-        json!({"name": "coin.GAS", "args": []})
-    ]);
+    key_pair.clist = Some(vec![Capability::gas()]);
 
-    let creation_time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64 - 100;
-    let meta = lang::mk_meta(&format!("k:{}", key_pair.public_key), target_chain_id, 0.0000001, 60000, creation_time as u64, 15000);
+    let meta = lang::mk_meta_with_policy(&format!("k:{}", key_pair.public_key), target_chain_id, 0.0000001, 60000, policy)?;
 
     let cmd = json!({
         "type": "cont",
@@ -264,10 +436,10 @@ pub fn crosschain_complete(pact_id: &str,
         "rollback": false,
         "step": 1,
         "proof": proof,
-        "envData": {"ks": {"pred": "keys-all", "keys": [receiver_public_key]}},
+        "envData": receiver_env(receiver_public_key).to_value(),
         "meta": meta,
         "networkId": network_id,
-        "nonce": chrono::Utc::now().to_rfc3339(),
+        "nonce": next_nonce(),
         "keyPairs": [json!({
             "publicKey": key_pair.public_key,
             "secretKey": key_pair.secret_key,
@@ -275,7 +447,7 @@ pub fn crosschain_complete(pact_id: &str,
         })]
     });
 
-    fetch::send(&cmd, &api_host, false)
+    apply_gas_payer(&cmd, gas_payer)
 }
 
 /// Perform a full cross-chain transfer lifecycle (initiate on source chain, poll, obtain SPV, submit continuation on target chain, poll final).
@@ -283,7 +455,7 @@ pub fn crosschain_complete(pact_id: &str,
 /// 1. Submit cross-chain transfer to source chain
 /// 2. Listen for transaction confirmation on source chain
 /// 3. Poll for SPV proof creation (pollCreateSpv equivalent)
-/// 4. Submit continuation transaction to target chain
+/// 4. Submit continuation transaction to target chain (rebuilt and re-signed if it expires before being mined)
 /// 5. Poll for continuation confirmation on target chain (matching JavaScript pollRequestKey)
 ///
 /// Returns a JSON object aggregating all intermediate artifacts:
@@ -295,6 +467,7 @@ pub fn crosschain_complete(pact_id: &str,
 ///   "init_status": "success",
 ///   "spv_proof": <proof string>,
 ///   "request_key_complete": <hash>,
+///   "previous_request_keys_complete": [<hash>, ...],   // only if the continuation had to be re-signed
 ///   "complete_result": {...},
 ///   "final_poll_result": {...},
 ///   "final_status": "success"
@@ -302,11 +475,12 @@ pub fn crosschain_complete(pact_id: &str,
 ///
 /// Errors (network / parsing issues) are surfaced inline in returned JSON fields; caller should inspect
 /// "error" keys. Function is best-effort; if a stage fails it stops early and returns what it has.
+#[allow(clippy::too_many_arguments)]
 pub fn crosschain_transfer_full(token_address: &str,
                                 sender_account: &str,
                                 receiver_account: &str,
                                 receiver_public_key: &str,
-                                amount: f64,
+                                amount: impl IntoPactDecimal,
                                 key_pair: KeyPair,
                                 source_chain_id: &str,
                                 target_chain_id: &str,
@@ -328,7 +502,7 @@ pub fn crosschain_transfer_full(token_address: &str,
 
     // 1. Initiate
     if cfg.verbose { println!("[xchain] initiating transfer..."); }
-    let init_res = crosschain_transfer(
+    let init_res = crosschain_transfer_with_policy(
         token_address,
         sender_account,
         receiver_account,
@@ -339,8 +513,10 @@ pub fn crosschain_transfer_full(token_address: &str,
         target_chain_id,
         network_id,
         x_chain_v1,
+        &cfg.time_policy,
+        &cfg.gas_payer,
     );
-    let request_key = init_res.get("requestKeys").and_then(|v| v.as_array()).and_then(|arr| arr.get(0)).and_then(|v| v.as_str()).map(|s| s.to_string());
+    let request_key = init_res.get("requestKeys").and_then(|v| v.as_array()).and_then(|arr| arr.first()).and_then(|v| v.as_str()).map(|s| s.to_string());
     artifacts["init_result"] = init_res.clone();
     if request_key.is_none() { artifacts["error"] = json!("missing request key from initiation"); return artifacts; }
     let rk = request_key.unwrap();
@@ -349,7 +525,10 @@ pub fn crosschain_transfer_full(token_address: &str,
     // 2. Poll for initiation transaction success first (faster than listen)
     if cfg.verbose { println!("[xchain] polling for transaction status..."); }
     let poll_req = json!({"requestKeys": [rk.clone()]});
-    let source_api = get_api_host(network_id, source_chain_id);
+    let source_api = match try_get_api_host(network_id, source_chain_id) {
+        Ok(host) => host,
+        Err(e) => { artifacts["error"] = json!(e); return artifacts; }
+    };
     let mut attempt = 0;
     loop {
         attempt += 1;
@@ -415,16 +594,28 @@ pub fn crosschain_transfer_full(token_address: &str,
 
     // 6. Submit continuation on target chain
     if cfg.verbose { println!("[xchain] submitting continuation on target chain..."); }
-    let complete_res = crosschain_complete(&pact_id, &spv_string, receiver_account, receiver_public_key, amount, key_pair.clone(), target_chain_id, network_id);
-    let request_key_complete = complete_res.get("requestKeys").and_then(|v| v.as_array()).and_then(|arr| arr.get(0)).and_then(|v| v.as_str()).map(|s| s.to_string());
+    let target_api = match try_get_api_host(network_id, target_chain_id) {
+        Ok(host) => host,
+        Err(e) => { artifacts["error"] = json!(e); return artifacts; }
+    };
+    let refresh_cfg = RefreshSendConfig { time_policy: cfg.time_policy.clone(), ..RefreshSendConfig::default() };
+    let complete_cmd = match crosschain_complete_cmd(&pact_id, &spv_string, key_pair.clone(), receiver_public_key, target_chain_id, network_id, &cfg.time_policy, &cfg.gas_payer) {
+        Ok(cmd) => cmd,
+        Err(e) => { artifacts["error"] = json!(e); return artifacts; }
+    };
+    let (complete_res, mut complete_cmd) = fetch::send_refreshing_tracked(&complete_cmd, &target_api, &refresh_cfg);
+    let mut previous_keys: Vec<Value> = complete_res.get("previousRequestKeys").and_then(|v| v.as_array()).cloned().unwrap_or_default();
+    if !previous_keys.is_empty() {
+        artifacts["previous_request_keys_complete"] = json!(previous_keys);
+    }
+    let request_key_complete = complete_res.get("requestKeys").and_then(|v| v.as_array()).and_then(|arr| arr.first()).and_then(|v| v.as_str()).map(|s| s.to_string());
     artifacts["complete_result"] = complete_res.clone();
     if request_key_complete.is_none() { artifacts["error"] = json!("missing request key from completion step"); return artifacts; }
-    let rk_complete = request_key_complete.unwrap();
+    let mut rk_complete = request_key_complete.unwrap();
     artifacts["request_key_complete"] = json!(rk_complete);
 
     // 7. Poll for final completion (matching JavaScript reference using pollRequestKey)
-    let final_poll_req = json!({"requestKeys": [rk_complete.clone()]});
-    let target_api = get_api_host(network_id, target_chain_id);
+    let mut final_poll_req = json!({"requestKeys": [rk_complete.clone()]});
     attempt = 0;
     let final_poll_result = loop {
         attempt += 1;
@@ -437,6 +628,22 @@ pub fn crosschain_transfer_full(token_address: &str,
         if res.get(&rk_complete).is_some() {
             break res;
         }
+        // A continuation that expired unmined never will be: rebuild, re-sign and resubmit it
+        if command_expired(&complete_cmd, &cfg.time_policy) {
+            if cfg.verbose { println!("[xchain] continuation {} expired unmined, resubmitting", rk_complete); }
+            let (res, cmd) = fetch::send_refreshing_tracked(&fetch::refresh_command(&complete_cmd, &cfg.time_policy), &target_api, &refresh_cfg);
+            let Some(new_rk) = res.get("requestKeys").and_then(|v| v.as_array()).and_then(|arr| arr.first()).and_then(|v| v.as_str()).map(|s| s.to_string()) else {
+                artifacts["error"] = json!(format!("resubmitting expired continuation failed: {}", res));
+                return artifacts;
+            };
+            previous_keys.push(json!(rk_complete));
+            previous_keys.extend(res.get("previousRequestKeys").and_then(|v| v.as_array()).cloned().unwrap_or_default());
+            artifacts["previous_request_keys_complete"] = json!(previous_keys);
+            artifacts["request_key_complete"] = json!(new_rk);
+            complete_cmd = cmd;
+            rk_complete = new_rk;
+            final_poll_req = json!({"requestKeys": [rk_complete.clone()]});
+        }
         if cfg.attempts_final > 0 && attempt >= cfg.attempts_final {
             artifacts["error"] = json!(format!("max attempts ({}) reached waiting for final completion", cfg.attempts_final));
             return artifacts;
@@ -467,19 +674,61 @@ pub fn crosschain_transfer_full(token_address: &str,
     artifacts
 }
 
+// Whether a command object's meta says it can no longer be mined
+fn command_expired(cmd: &Value, policy: &TimePolicy) -> bool {
+    let meta = &cmd["meta"];
+    match (meta.get("creationTime").and_then(|v| v.as_u64()), meta.get("ttl").and_then(|v| v.as_u64())) {
+        (Some(creation_time), Some(ttl)) => policy.is_expired(creation_time, ttl),
+        _ => false,
+    }
+}
+
 pub fn get_contract_code(namespace_dot_module: &str, network_id: &str, chain_id: &str) -> Value {
-    let describe_code = format!("(describe-module \"{}\")", namespace_dot_module);
-    let creation_time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
-    let meta = lang::mk_meta("not real", chain_id, 0.0000001, 60000, creation_time, 5000);
+    let describe_code = PactExpr::app(PactExpr::Symbol("describe-module".to_string()), vec![PactExpr::string(namespace_dot_module)]).to_string();
+    local_code(&describe_code, chain_id, network_id)
+}
+
+// Run read-only `code` through /local without signers
+fn local_code(code: &str, chain_id: &str, network_id: &str) -> Value {
+    let meta = match lang::mk_meta_with_policy("not real", chain_id, 0.0000001, 60000, &default_policy()) {
+        Ok(meta) => meta,
+        Err(e) => return json!({"error": e}),
+    };
     let cmd = json!({
-        "pactCode": describe_code,
+        "pactCode": code,
         "envData": {},
         "meta": meta,
         "networkId": network_id,
-        "nonce": chrono::Utc::now().to_rfc3339(),
+        "nonce": next_nonce(),
         "keyPairs": []
     });
-    let api_host = get_api_host(network_id, chain_id);
-    let res = fetch::local(&cmd, &api_host);
-    res
-}
\ No newline at end of file
+    match try_get_api_host(network_id, chain_id) {
+        Ok(api_host) => fetch::local(&cmd, &api_host),
+        Err(e) => json!({"error": e}),
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_precisions_skip_the_lookup() {
+        set_token_precision("free.six", "0", "nowhere", 6);
+        assert_eq!(token_precision("free.six", "0", "nowhere"), Ok(6));
+        assert_eq!(checked_amount("free.six", "1.000001", "0", "nowhere"), Ok(PactDecimal::parse("1.000001").unwrap()));
+        assert!(checked_amount("free.six", "1.0000001", "0", "nowhere").unwrap_err().contains("more than the allowed 6"));
+        assert_eq!(token_precision("coin", "0", "nowhere"), Ok(COIN_PRECISION));
+        assert!(token_precision("free.other", "0", "nowhere").unwrap_err().contains("Unsupported network_id: nowhere"));
+    }
+
+    #[test]
+    fn unknown_networks_are_errors_not_panics() {
+        let kp = KeyPair { public_key: "ab".to_string(), secret_key: "cd".to_string(), ..Default::default() };
+        let expected = json!({"error": "Unsupported network_id: nowhere"});
+        assert_eq!(token_transfer("coin", "k:a", "k:b", "b", "1.0", kp.clone(), "0", "nowhere"), expected);
+        assert_eq!(crosschain_transfer("coin", "k:a", "k:b", "b", 1, kp.clone(), "0", "1", "nowhere", None), expected);
+        assert_eq!(crosschain_complete("pid", "proof", "k:b", "b", "1.0", kp, "1", "nowhere"), expected);
+        assert!(poll_create_spv("rk", "0", "1", "nowhere", Some(1), 0).is_err());
+        assert_eq!(get_contract_code("coin", "nowhere", "0"), expected);
+    }
+}
@@ -0,0 +1,489 @@
+// Types module: strongly typed Pact command model
+//
+// Field order in every struct is alphabetical on purpose: the Value-based
+// builders serialize through serde_json's sorted maps, and keeping the same
+// order here makes both paths emit byte-identical `cmd` strings (and therefore
+// identical hashes).
+use crate::crypto::{b64_url_encoded_hash, hash_bin, sign, verify};
+use crate::decimal::PactDecimal;
+use crate::utils::KeyPair;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Number, Value};
+
+/// A capability granted to a signer, e.g. `{"name": "coin.GAS", "args": []}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Capability {
+    pub args: Vec<Value>,
+    pub name: String,
+}
+
+impl Capability {
+    pub fn new(name: &str, args: Vec<Value>) -> Self {
+        Self { args, name: name.to_string() }
+    }
+
+    /// `coin.GAS`: pay for the transaction's gas.
+    pub fn gas() -> Self {
+        Self::new("coin.GAS", vec![])
+    }
+
+    /// `coin.TRANSFER`: move `amount` KDA from `sender` to `receiver`.
+    pub fn transfer(sender: &str, receiver: &str, amount: impl Into<PactDecimal>) -> Self {
+        Self::fungible_transfer("coin", sender, receiver, amount)
+    }
+
+    /// `coin.TRANSFER_XCHAIN`: move `amount` KDA from `sender` to `receiver` on `target_chain`.
+    pub fn transfer_xchain(sender: &str, receiver: &str, amount: impl Into<PactDecimal>, target_chain: &str) -> Self {
+        Self::fungible_transfer_xchain("coin", sender, receiver, amount, target_chain)
+    }
+
+    /// `coin.ROTATE`: change the guard of `account`.
+    pub fn rotate(account: &str) -> Self {
+        Self::new("coin.ROTATE", vec![json!(account)])
+    }
+
+    /// `TRANSFER` of any fungible-v2 token, e.g. module `free.my-token`.
+    pub fn fungible_transfer(module: &str, sender: &str, receiver: &str, amount: impl Into<PactDecimal>) -> Self {
+        Self::new(&format!("{}.TRANSFER", module), vec![json!(sender), json!(receiver), amount.into().to_json()])
+    }
+
+    /// `TRANSFER_XCHAIN` of any fungible-xchain-v1 token.
+    pub fn fungible_transfer_xchain(module: &str, sender: &str, receiver: &str, amount: impl Into<PactDecimal>, target_chain: &str) -> Self {
+        Self::new(&format!("{}.TRANSFER_XCHAIN", module), vec![json!(sender), json!(receiver), amount.into().to_json(), json!(target_chain)])
+    }
+
+    /// `GAS_PAYER` of a gas station `module`: pay gas for `user` up to `gas_limit` at `gas_price`.
+    pub fn gas_payer(module: &str, user: &str, gas_limit: u64, gas_price: impl Into<PactDecimal>) -> Self {
+        Self::new(&format!("{}.GAS_PAYER", module), vec![json!(user), json!({"int": gas_limit}), gas_price.into().to_json()])
+    }
+}
+
+/// Public chain metadata (`meta` field of a command).
+///
+/// `gas_price` is kept as the JSON number it was given as, so `1` and `1.0`
+/// serialize (and hash) the way they came in. Keys Pact does not define are
+/// kept in `extra` and serialized back in sorted order with the others, so a
+/// caller's meta object comes out byte for byte as it went in.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PublicMeta {
+    pub chain_id: String,
+    pub creation_time: u64,
+    pub gas_limit: u64,
+    pub gas_price: Number,
+    pub sender: String,
+    pub ttl: u64,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Default for PublicMeta {
+    fn default() -> Self {
+        Self { chain_id: String::new(), creation_time: 0, gas_limit: 0, gas_price: Number::from(0), sender: String::new(), ttl: 0, extra: Map::new() }
+    }
+}
+
+// Through a sorted map, so keys in `extra` land where `json!` would put them
+impl Serialize for PublicMeta {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = self.extra.clone();
+        map.insert("chainId".to_string(), json!(self.chain_id));
+        map.insert("creationTime".to_string(), json!(self.creation_time));
+        map.insert("gasLimit".to_string(), json!(self.gas_limit));
+        map.insert("gasPrice".to_string(), Value::Number(self.gas_price.clone()));
+        map.insert("sender".to_string(), json!(self.sender));
+        map.insert("ttl".to_string(), json!(self.ttl));
+        map.serialize(serializer)
+    }
+}
+
+impl PublicMeta {
+    /// Panics if `gas_price` is NaN or infinite, which JSON cannot represent.
+    pub fn new(sender: &str, chain_id: &str, gas_price: f64, gas_limit: u64, creation_time: u64, ttl: u64) -> Self {
+        let gas_price = Number::from_f64(gas_price).expect("gas price must be a finite number");
+        Self { chain_id: chain_id.to_string(), creation_time, gas_limit, gas_price, sender: sender.to_string(), ttl, extra: Map::new() }
+    }
+
+    /// UNIX time after which nodes reject the command.
+    pub fn expires_at(&self) -> u64 {
+        self.creation_time.saturating_add(self.ttl)
+    }
+
+    /// Conversion from the JSON accepted by `api::prepare_exec_cmd`. A missing
+    /// field or one of the wrong type, e.g. a fractional `creationTime`, is an
+    /// error; unknown keys are kept in `extra`.
+    pub fn from_value(meta: &Value) -> Result<Self, String> {
+        serde_json::from_value(meta.clone()).map_err(|e| format!("malformed meta: {}", e))
+    }
+}
+
+/// Signature scheme of a signer. Pact assumes ED25519 when `scheme` is omitted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SignerScheme {
+    #[serde(rename = "ED25519")]
+    Ed25519,
+    WebAuthn,
+}
+
+/// A public key that must sign the command, optionally scoped to capabilities.
+///
+/// `scheme` and `addr` are omitted from the JSON when unset, which keeps the
+/// output identical to a plain `{"pubKey", "clist"}` signer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Signer {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub addr: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clist: Option<Vec<Capability>>,
+    pub pub_key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scheme: Option<SignerScheme>,
+}
+
+impl Signer {
+    pub fn new(pub_key: &str, clist: Option<Vec<Capability>>) -> Self {
+        Self { addr: None, clist, pub_key: pub_key.to_string(), scheme: None }
+    }
+
+    pub fn with_scheme(mut self, scheme: SignerScheme) -> Self {
+        self.scheme = Some(scheme);
+        self
+    }
+
+    pub fn with_addr(mut self, addr: &str) -> Self {
+        self.addr = Some(addr.to_string());
+        self
+    }
+
+    /// Check the public key against the scheme and that `addr`, when set, matches it.
+    ///
+    /// ED25519 keys are 64 hex characters. WebAuthn keys are hex encoded and
+    /// may carry Pact's `WEBAUTHN-` prefix. In both schemes the address of a
+    /// signer is its public key.
+    pub fn validate(&self) -> Result<(), String> {
+        let key = self.pub_key.as_str();
+        match self.scheme.unwrap_or(SignerScheme::Ed25519) {
+            SignerScheme::Ed25519 => {
+                if key.len() != 64 || !key.chars().all(|c| c.is_ascii_hexdigit()) {
+                    return Err(format!("ED25519 public key must be 64 hex characters: {}", key));
+                }
+            }
+            SignerScheme::WebAuthn => {
+                let raw = key.strip_prefix("WEBAUTHN-").unwrap_or(key);
+                if raw.is_empty() || !raw.len().is_multiple_of(2) || !raw.chars().all(|c| c.is_ascii_hexdigit()) {
+                    return Err(format!("WebAuthn public key must be hex encoded: {}", key));
+                }
+            }
+        }
+        if let Some(addr) = &self.addr {
+            if !addr.eq_ignore_ascii_case(key) {
+                return Err(format!("signer addr {} does not match public key {}", addr, key));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<&KeyPair> for Signer {
+    fn from(kp: &KeyPair) -> Self {
+        Self { addr: kp.addr.clone(), clist: kp.clist.clone(), pub_key: kp.public_key.clone(), scheme: kp.scheme }
+    }
+}
+
+/// A verifier plugin invocation (Pact 5), e.g. Hyperlane message verification.
+///
+/// The plugin named `name` checks `proof` and, if valid, grants the
+/// capabilities in `clist` the same way a signature would.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Verifier {
+    pub clist: Vec<Capability>,
+    pub name: String,
+    pub proof: Value,
+}
+
+impl Verifier {
+    pub fn new(name: &str, proof: Value, clist: Vec<Capability>) -> Self {
+        Self { clist, name: name.to_string(), proof }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExecPayload {
+    pub code: String,
+    pub data: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContPayload {
+    pub data: Value,
+    pub pact_id: String,
+    pub proof: Option<String>,
+    pub rollback: bool,
+    pub step: u64,
+}
+
+/// Command payload: `{"exec": {...}}` or `{"cont": {...}}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Payload {
+    Exec(ExecPayload),
+    Cont(ContPayload),
+}
+
+impl Payload {
+    pub fn exec(code: &str, data: Value) -> Self {
+        Payload::Exec(ExecPayload { code: code.to_string(), data })
+    }
+
+    pub fn cont(pact_id: &str, rollback: bool, step: u64, proof: Option<String>, data: Value) -> Self {
+        Payload::Cont(ContPayload { data, pact_id: pact_id.to_string(), proof, rollback, step })
+    }
+}
+
+/// The command whose JSON serialization is hashed and signed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Command {
+    pub meta: PublicMeta,
+    pub network_id: Option<String>,
+    pub nonce: String,
+    pub payload: Payload,
+    pub signers: Vec<Signer>,
+    /// Omitted from the JSON when empty, as nodes before Pact 5 reject the field.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub verifiers: Vec<Verifier>,
+}
+
+impl Command {
+    /// The exact string that goes into the `cmd` field of a signed command.
+    pub fn to_cmd_string(&self) -> String {
+        serde_json::to_string(self).expect("command serializes to JSON")
+    }
+
+    /// Base64url blake2b hash of `to_cmd_string()`, i.e. the request key.
+    pub fn hash(&self) -> String {
+        b64_url_encoded_hash(&hash_bin(&self.to_cmd_string()))
+    }
+
+    /// Serialize without signing; one `null` signature slot per signer.
+    pub fn to_unsigned(&self) -> UnsignedCommand {
+        let cmd = self.to_cmd_string();
+        let hash = b64_url_encoded_hash(&hash_bin(&cmd));
+        UnsignedCommand { cmd, hash, sigs: vec![None; self.signers.len()] }
+    }
+
+    /// Serialize and sign with every key pair, in order. A key pair without
+    /// a usable secret key (64 hex characters) is an error.
+    pub fn sign(&self, key_pairs: &[KeyPair]) -> Result<SignedCommand, String> {
+        let cmd = self.to_cmd_string();
+        let hash = b64_url_encoded_hash(&hash_bin(&cmd));
+        let sigs = key_pairs.iter()
+            .map(|kp| check_secret_key(kp).map(|_| Sig { sig: sign(&cmd, &kp.secret_key).1 }))
+            .collect::<Result<_, _>>()?;
+        Ok(SignedCommand { cmd, hash, sigs })
+    }
+}
+
+// `crypto::sign` panics on anything but 32 hex-encoded bytes
+fn check_secret_key(kp: &KeyPair) -> Result<(), String> {
+    if hex::decode(&kp.secret_key).map(|b| b.len()) != Ok(32) {
+        return Err(format!("no usable secret key for {}", kp.public_key));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Sig {
+    pub sig: String,
+}
+
+/// A command ready for `/send` or `/local`: `{"cmd": ..., "hash": ..., "sigs": [...]}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignedCommand {
+    pub cmd: String,
+    pub hash: String,
+    pub sigs: Vec<Sig>,
+}
+
+impl SignedCommand {
+    pub fn to_value(&self) -> Value {
+        serde_json::to_value(self).expect("signed command serializes to JSON")
+    }
+
+    /// Parse the embedded `cmd` string back into a `Command`.
+    pub fn command(&self) -> Result<Command, String> {
+        serde_json::from_str(&self.cmd).map_err(|e| format!("malformed cmd: {}", e))
+    }
+
+    /// UNIX time after which nodes reject this command.
+    pub fn expiry_time(&self) -> Result<u64, String> {
+        Ok(self.command()?.meta.expires_at())
+    }
+}
+
+/// A command awaiting signatures from external signers.
+///
+/// Serializes as `{"cmd": ..., "hash": ..., "sigs": [null, ...]}` with slots in
+/// signer order. Fill slots with `add_signature` (or `sign_with` for local
+/// keys) and convert with `into_signed` once every signer has signed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnsignedCommand {
+    pub cmd: String,
+    pub hash: String,
+    pub sigs: Vec<Option<Sig>>,
+}
+
+impl UnsignedCommand {
+    pub fn to_value(&self) -> Value {
+        serde_json::to_value(self).expect("unsigned command serializes to JSON")
+    }
+
+    pub fn command(&self) -> Result<Command, String> {
+        serde_json::from_str(&self.cmd).map_err(|e| format!("malformed cmd: {}", e))
+    }
+
+    pub fn expiry_time(&self) -> Result<u64, String> {
+        Ok(self.command()?.meta.expires_at())
+    }
+
+    /// Public keys of the signers, in slot order.
+    pub fn signer_keys(&self) -> Result<Vec<String>, String> {
+        Ok(self.command()?.signers.into_iter().map(|s| s.pub_key).collect())
+    }
+
+    // Signer keys, checked to line up one-to-one with the signature slots
+    fn slot_keys(&self) -> Result<Vec<String>, String> {
+        let keys = self.signer_keys()?;
+        if keys.len() != self.sigs.len() {
+            return Err(format!("command has {} signers but {} signature slots", keys.len(), self.sigs.len()));
+        }
+        Ok(keys)
+    }
+
+    /// Fill the first empty slot for `pub_key` with a hex signature over the
+    /// hash, after verifying it. A key listed as several signers is added once per slot.
+    pub fn add_signature(&mut self, pub_key: &str, sig: &str) -> Result<(), String> {
+        let keys = self.slot_keys()?;
+        let idx = keys.iter().zip(&self.sigs).position(|(k, s)| k == pub_key && s.is_none()).ok_or_else(|| {
+            if keys.iter().any(|k| k == pub_key) {
+                format!("every slot of {} is already signed", pub_key)
+            } else {
+                format!("{} is not a signer of this command", pub_key)
+            }
+        })?;
+        let key_ok = hex::decode(pub_key).ok().map(|b| ed25519_dalek::PublicKey::from_bytes(&b).is_ok()).unwrap_or(false);
+        if !key_ok {
+            return Err(format!("invalid public key: {}", pub_key));
+        }
+        if hex::decode(sig).map(|b| b.len()) != Ok(64) {
+            return Err(format!("invalid signature for {}: expected 128 hex characters", pub_key));
+        }
+        if !verify(&self.cmd, pub_key, sig) {
+            return Err(format!("signature for {} does not match command hash", pub_key));
+        }
+        let slot = self.sigs.get_mut(idx).ok_or_else(|| format!("no signature slot {}", idx))?;
+        *slot = Some(Sig { sig: sig.to_string() });
+        Ok(())
+    }
+
+    /// Sign the first empty slot of `kp.public_key` with its secret key.
+    pub fn sign_with(&mut self, kp: &KeyPair) -> Result<(), String> {
+        check_secret_key(kp)?;
+        let (_hash, sig) = sign(&self.cmd, &kp.secret_key);
+        self.add_signature(&kp.public_key, &sig)
+    }
+
+    /// Public keys whose slots are still empty.
+    pub fn missing_signers(&self) -> Result<Vec<String>, String> {
+        let keys = self.slot_keys()?;
+        Ok(keys.into_iter().zip(&self.sigs).filter(|(_, s)| s.is_none()).map(|(k, _)| k).collect())
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.missing_signers().is_ok_and(|m| m.is_empty())
+    }
+
+    pub fn into_signed(self) -> Result<SignedCommand, String> {
+        let missing = self.missing_signers()?;
+        if !missing.is_empty() {
+            return Err(format!("missing signatures from: {}", missing.join(", ")));
+        }
+        Ok(SignedCommand { cmd: self.cmd, hash: self.hash, sigs: self.sigs.into_iter().flatten().collect() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn meta_round_trips_byte_for_byte() {
+        let src = r#"{"aaa":[1],"chainId":"0","creationTime":5,"gasLimit":1000,"gasPrice":1,"platform":{"x":true},"sender":"k:a","ttl":600}"#;
+        let meta = PublicMeta::from_value(&serde_json::from_str(src).unwrap()).unwrap();
+        assert_eq!(meta.extra.len(), 2);
+        assert_eq!(serde_json::to_string(&meta).unwrap(), src);
+    }
+
+    #[test]
+    fn cmd_string_matches_the_json_macro_output() {
+        let meta = json!({"creationTime": 5, "ttl": 600, "gasLimit": 1000, "chainId": "0", "gasPrice": 0.00000001, "sender": "k:a"});
+        let signers = json!([{"pubKey": "ab", "clist": [{"name": "coin.GAS", "args": []}, {"name": "coin.TRANSFER", "args": ["k:a", "k:b", {"decimal": "1.0"}]}]}, {"pubKey": "cd"}]);
+        let mut command = Command {
+            meta: PublicMeta::from_value(&meta).unwrap(),
+            network_id: Some("testnet04".to_string()),
+            nonce: "2024-01-01T00:00:00Z".to_string(),
+            payload: Payload::exec("(coin.transfer \"k:a\" \"k:b\" 1.0)", json!({"ks": {"keys": ["ab"], "pred": "keys-all"}})),
+            signers: serde_json::from_value(signers.clone()).unwrap(),
+            verifiers: vec![],
+        };
+        let exec = json!({
+            "networkId": "testnet04",
+            "payload": {"exec": {"data": {"ks": {"keys": ["ab"], "pred": "keys-all"}}, "code": "(coin.transfer \"k:a\" \"k:b\" 1.0)"}},
+            "signers": signers,
+            "meta": meta,
+            "nonce": "2024-01-01T00:00:00Z"
+        });
+        assert_eq!(command.to_cmd_string(), exec.to_string());
+
+        command.network_id = None;
+        command.payload = Payload::cont("pid", false, 1, None, json!({}));
+        let cont = json!({
+            "networkId": null,
+            "payload": {"cont": {"proof": null, "pactId": "pid", "rollback": false, "step": 1, "data": {}}},
+            "signers": signers,
+            "meta": meta,
+            "nonce": "2024-01-01T00:00:00Z"
+        });
+        assert_eq!(command.to_cmd_string(), cont.to_string());
+    }
+
+    #[test]
+    fn signing_without_a_usable_secret_key_is_an_error() {
+        let command = Command {
+            meta: PublicMeta::new("k:a", "0", 0.00000001, 1000, 5, 600),
+            network_id: Some("testnet04".to_string()),
+            nonce: "n".to_string(),
+            payload: Payload::exec("(+ 1 2)", json!({})),
+            signers: vec![Signer::new("ab", None)],
+            verifiers: vec![],
+        };
+        for secret in ["", "zz", "abcd"] {
+            let kp = KeyPair { public_key: "ab".to_string(), secret_key: secret.to_string(), ..Default::default() };
+            assert_eq!(command.sign(std::slice::from_ref(&kp)), Err("no usable secret key for ab".to_string()));
+            assert!(command.to_unsigned().sign_with(&kp).is_err());
+        }
+        let prepared = crate::api::prepare_exec_cmd("(+ 1 2)", json!({}), json!({"chainId": "0", "creationTime": 5, "gasLimit": 1, "gasPrice": 1, "sender": "s", "ttl": 600}),
+            None, Some("n".to_string()), Some(vec![KeyPair { public_key: "ab".to_string(), ..Default::default() }]));
+        assert_eq!(prepared, json!({"error": "no usable secret key for ab"}));
+    }
+
+    #[test]
+    fn meta_rejects_missing_and_mistyped_fields() {
+        let err = PublicMeta::from_value(&json!({"chainId": "0", "creationTime": 5, "gasLimit": 1, "gasPrice": 1, "ttl": 600})).unwrap_err();
+        assert!(err.contains("missing field `sender`"), "{}", err);
+        let err = PublicMeta::from_value(&json!({"chainId": "0", "creationTime": 5.5, "gasLimit": 1, "gasPrice": 1, "sender": "s", "ttl": 600})).unwrap_err();
+        assert!(err.starts_with("malformed meta"), "{}", err);
+    }
+}
@@ -22,20 +22,17 @@ pub fn int_to_hex(num: u64) -> String {
         vec![item]
     }
 
-    #[derive(Debug, Clone)]
+    #[derive(Debug, Clone, Default)]
     pub struct KeyPair {
         pub public_key: String,
         pub secret_key: String,
-        pub clist: Option<Vec<serde_json::Value>>, // capability objects: {name, args}
+        pub clist: Option<Vec<crate::types::Capability>>, // None or empty = unscoped
+        pub scheme: Option<crate::types::SignerScheme>, // None = ED25519 (omitted from the command)
+        pub addr: Option<String>, // signer address; must match the public key when set
     }
 
     pub fn mk_signer(kp: &KeyPair) -> serde_json::Value {
-        let mut obj = serde_json::Map::new();
-        obj.insert("pubKey".to_string(), serde_json::Value::String(kp.public_key.clone()));
-        if let Some(clist) = &kp.clist {
-            obj.insert("clist".to_string(), serde_json::Value::Array(clist.clone()));
-        }
-        serde_json::Value::Object(obj)
+        serde_json::to_value(crate::types::Signer::from(kp)).expect("signer serializes to JSON")
     }
 
     pub fn pull_sig(s: &serde_json::Value) -> serde_json::Value {
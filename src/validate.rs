@@ -0,0 +1,220 @@
+// Validate module: client-side static checks of prepared commands before they reach a node
+use crate::clock::{default_policy, TimePolicy};
+use crate::gas::BLOCK_GAS_LIMIT;
+use crate::types::Command;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::fmt;
+
+/// Limits a command is checked against.
+///
+///  block_gas_limit       -> largest acceptable gasLimit (default 150000)
+///  max_payload_bytes     -> largest acceptable `cmd` string in bytes (default 100000)
+///  future_tolerance_secs -> how far creationTime may lie ahead of the clock (default 0)
+///  time_policy           -> clock and max_ttl (default `clock::default_policy()`, max_ttl 24h)
+#[derive(Debug, Clone)]
+pub struct ValidationConfig {
+    pub block_gas_limit: u64,
+    pub max_payload_bytes: usize,
+    pub future_tolerance_secs: u64,
+    pub time_policy: TimePolicy,
+}
+
+impl Default for ValidationConfig {
+    fn default() -> Self {
+        Self { block_gas_limit: BLOCK_GAS_LIMIT, max_payload_bytes: 100_000, future_tolerance_secs: 0, time_policy: default_policy() }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IssueKind {
+    Malformed,
+    GasLimitTooHigh,
+    InvalidTtl,
+    CreationTimeInFuture,
+    ChainIdMismatch,
+    NetworkIdMissing,
+    NetworkIdMismatch,
+    PayloadTooLarge,
+    DuplicateSigner,
+}
+
+/// One problem found in a command. `index` is the command's position in a batch.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValidationIssue {
+    pub index: usize,
+    pub kind: IssueKind,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "command {}: {}", self.index, self.message)
+    }
+}
+
+/// Check a typed command. `api_host`, when given, is the chainweb Pact endpoint
+/// it will be sent to (`.../chainweb/0.0/<network>/chain/<id>/pact`), used to
+/// catch chainId and networkId mismatches.
+pub fn validate_command(command: &Command, api_host: Option<&str>, cfg: &ValidationConfig) -> Vec<ValidationIssue> {
+    check(0, command, command.to_cmd_string().len(), api_host, cfg)
+}
+
+/// Check every signed command (`{cmd, hash, sigs}`) of a `{"cmds": [...]}` send body,
+/// or a single signed command, reporting all problems found.
+pub fn validate_send_body(body: &Value, api_host: Option<&str>, cfg: &ValidationConfig) -> Vec<ValidationIssue> {
+    let cmds = match body.get("cmds").and_then(|c| c.as_array()) {
+        Some(arr) => arr.clone(),
+        None => vec![body.clone()],
+    };
+    let mut issues = Vec::new();
+    for (index, signed) in cmds.iter().enumerate() {
+        let Some(cmd_str) = signed.get("cmd").and_then(|c| c.as_str()) else {
+            issues.push(issue(index, IssueKind::Malformed, "no cmd string".to_string()));
+            continue;
+        };
+        match serde_json::from_str::<Command>(cmd_str) {
+            Ok(command) => issues.extend(check(index, &command, cmd_str.len(), api_host, cfg)),
+            Err(e) => issues.push(issue(index, IssueKind::Malformed, format!("cmd is not a valid command: {}", e))),
+        }
+    }
+    issues
+}
+
+/// Inline error object for a failed validation, in the shape the send helpers return.
+pub fn issues_to_error(issues: &[ValidationIssue]) -> Value {
+    let summary: Vec<String> = issues.iter().map(|i| i.to_string()).collect();
+    json!({"error": format!("command validation failed: {}", summary.join("; ")), "issues": issues})
+}
+
+fn issue(index: usize, kind: IssueKind, message: String) -> ValidationIssue {
+    ValidationIssue { index, kind, message }
+}
+
+fn check(index: usize, command: &Command, cmd_len: usize, api_host: Option<&str>, cfg: &ValidationConfig) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    let meta = &command.meta;
+
+    if meta.gas_limit > cfg.block_gas_limit {
+        issues.push(issue(index, IssueKind::GasLimitTooHigh, format!("gasLimit {} exceeds the block gas limit of {}", meta.gas_limit, cfg.block_gas_limit)));
+    }
+    if let Err(e) = cfg.time_policy.validate_ttl(meta.ttl) {
+        issues.push(issue(index, IssueKind::InvalidTtl, e));
+    }
+    let now = cfg.time_policy.now();
+    if meta.creation_time > now + cfg.future_tolerance_secs {
+        issues.push(issue(index, IssueKind::CreationTimeInFuture, format!("creationTime {} is {}s in the future", meta.creation_time, meta.creation_time - now)));
+    }
+    if cmd_len > cfg.max_payload_bytes {
+        issues.push(issue(index, IssueKind::PayloadTooLarge, format!("cmd is {} bytes, above the limit of {}", cmd_len, cfg.max_payload_bytes)));
+    }
+
+    let network_id = command.network_id.as_deref().filter(|n| !n.is_empty());
+    if network_id.is_none() {
+        issues.push(issue(index, IssueKind::NetworkIdMissing, "networkId is missing".to_string()));
+    }
+    if let Some(host) = api_host {
+        if let Some(host_chain) = path_segment_after(host, "chain") {
+            if meta.chain_id != host_chain {
+                issues.push(issue(index, IssueKind::ChainIdMismatch, format!("chainId {:?} does not match chain {} of {}", meta.chain_id, host_chain, host)));
+            }
+        }
+        if let (Some(net), Some(host_net)) = (network_id, path_segment_after(host, "0.0")) {
+            if net != host_net {
+                issues.push(issue(index, IssueKind::NetworkIdMismatch, format!("networkId {:?} does not match network {} of {}", net, host_net, host)));
+            }
+        }
+    }
+
+    let mut seen = HashSet::new();
+    for signer in &command.signers {
+        if !seen.insert(signer.pub_key.to_lowercase()) {
+            issues.push(issue(index, IssueKind::DuplicateSigner, format!("signer {} appears more than once", signer.pub_key)));
+        }
+    }
+    issues
+}
+
+// ".../chainweb/0.0/testnet04/chain/1/pact" with "chain" -> "1"
+fn path_segment_after<'a>(url: &'a str, marker: &str) -> Option<&'a str> {
+    let mut segments = url.split('/');
+    segments.by_ref().find(|s| *s == marker)?;
+    segments.next().filter(|s| !s.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FixedClock;
+    use crate::types::{Payload, PublicMeta, Signer};
+    use std::sync::Arc;
+
+    const HOST: &str = "https://api.testnet.chainweb.com/chainweb/0.0/testnet04/chain/1/pact";
+
+    fn config() -> ValidationConfig {
+        let time_policy = TimePolicy { clock: Arc::new(FixedClock(10_000)), skew_secs: 0, ttl: 600, max_ttl: 86400 };
+        ValidationConfig { time_policy, ..ValidationConfig::default() }
+    }
+
+    fn command() -> Command {
+        Command {
+            meta: PublicMeta::new("k:a", "1", 0.00000001, 1000, 9_900, 600),
+            network_id: Some("testnet04".to_string()),
+            nonce: "n".to_string(),
+            payload: Payload::exec("(+ 1 2)", json!({})),
+            signers: vec![Signer::new("ab", None)],
+            verifiers: vec![],
+        }
+    }
+
+    fn kinds(issues: &[ValidationIssue]) -> Vec<IssueKind> {
+        issues.iter().map(|i| i.kind).collect()
+    }
+
+    #[test]
+    fn a_valid_command_has_no_issues() {
+        assert!(validate_command(&command(), Some(HOST), &config()).is_empty());
+    }
+
+    #[test]
+    fn every_problem_is_reported() {
+        let mut cmd = command();
+        cmd.meta = PublicMeta::new("k:a", "2", 0.00000001, 150_001, 10_001, 86_401);
+        cmd.network_id = Some("mainnet01".to_string());
+        cmd.signers = vec![Signer::new("ab", None), Signer::new("AB", None)];
+        let issues = validate_command(&cmd, Some(HOST), &config());
+        assert_eq!(kinds(&issues), [
+            IssueKind::GasLimitTooHigh,
+            IssueKind::InvalidTtl,
+            IssueKind::CreationTimeInFuture,
+            IssueKind::ChainIdMismatch,
+            IssueKind::NetworkIdMismatch,
+            IssueKind::DuplicateSigner,
+        ]);
+        assert_eq!(issues[2].to_string(), "command 0: creationTime 10001 is 1s in the future");
+        let tolerant = ValidationConfig { future_tolerance_secs: 1, ..config() };
+        assert!(!kinds(&validate_command(&cmd, Some(HOST), &tolerant)).contains(&IssueKind::CreationTimeInFuture));
+    }
+
+    #[test]
+    fn missing_network_and_oversized_payload() {
+        let mut cmd = command();
+        cmd.network_id = Some(String::new());
+        cmd.meta.ttl = 0;
+        let small = ValidationConfig { max_payload_bytes: 10, ..config() };
+        assert_eq!(kinds(&validate_command(&cmd, None, &small)), [IssueKind::InvalidTtl, IssueKind::PayloadTooLarge, IssueKind::NetworkIdMissing]);
+    }
+
+    #[test]
+    fn send_bodies_are_checked_per_command() {
+        let good = json!({"cmd": command().to_cmd_string(), "hash": "h", "sigs": []});
+        let body = json!({"cmds": [good, {"hash": "h"}, {"cmd": "{}"}]});
+        let issues = validate_send_body(&body, Some(HOST), &config());
+        assert_eq!(issues.iter().map(|i| (i.index, i.kind)).collect::<Vec<_>>(), [(1, IssueKind::Malformed), (2, IssueKind::Malformed)]);
+        let error = issues_to_error(&issues);
+        assert!(error["error"].as_str().unwrap().starts_with("command validation failed: command 1: no cmd string; command 2: "));
+        assert_eq!(error["issues"][0]["kind"], "malformed");
+    }
+}
@@ -0,0 +1,209 @@
+// Vanity module: multi-threaded search for k: accounts with recognizable public keys
+use crate::utils::KeyPair;
+use ed25519_dalek::{PublicKey, SecretKey};
+use regex::Regex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// Constraints and tuning for a vanity key search.
+///
+/// All constraints apply to the lowercase hex public key (the part after `k:`)
+/// and must all match for a key to be accepted.
+///
+///  prefix               -> hex the public key must start with
+///  suffix               -> hex the public key must end with
+///  pattern              -> regular expression the public key must match
+///  threads              -> worker threads (default: available parallelism)
+///  max_attempts         -> give up after this many keys (default 0 = infinite)
+///  progress_interval_ms -> interval between progress callbacks (default 1000)
+#[derive(Debug, Clone)]
+pub struct VanityConfig {
+    pub prefix: Option<String>,
+    pub suffix: Option<String>,
+    pub pattern: Option<String>,
+    pub threads: usize,
+    pub max_attempts: u64,
+    pub progress_interval_ms: u64,
+}
+
+impl Default for VanityConfig {
+    fn default() -> Self {
+        let threads = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        Self { prefix: None, suffix: None, pattern: None, threads, max_attempts: 0, progress_interval_ms: 1000 }
+    }
+}
+
+/// Snapshot of a running search, handed to the progress callback.
+#[derive(Debug, Clone)]
+pub struct VanityProgress {
+    pub attempts: u64,
+    pub elapsed: Duration,
+    /// Keys tried per second across all threads.
+    pub rate: f64,
+    /// Expected number of attempts, see `estimate_difficulty`.
+    pub expected_attempts: f64,
+}
+
+struct Matcher {
+    prefix: String,
+    suffix: String,
+    pattern: Option<Regex>,
+}
+
+impl Matcher {
+    fn new(config: &VanityConfig) -> Result<Self, String> {
+        estimate_difficulty(config)?;
+        let prefix = normalize_hex("prefix", config.prefix.as_deref())?;
+        let suffix = normalize_hex("suffix", config.suffix.as_deref())?;
+        let pattern = match &config.pattern {
+            Some(p) => Some(Regex::new(p).map_err(|e| format!("invalid pattern: {}", e))?),
+            None => None,
+        };
+        Ok(Self { prefix, suffix, pattern })
+    }
+
+    fn is_match(&self, public_hex: &str) -> bool {
+        public_hex.starts_with(&self.prefix)
+            && public_hex.ends_with(&self.suffix)
+            && self.pattern.as_ref().map(|re| re.is_match(public_hex)).unwrap_or(true)
+    }
+}
+
+fn normalize_hex(what: &str, value: Option<&str>) -> Result<String, String> {
+    let value = value.unwrap_or("").to_lowercase();
+    if let Some(c) = value.chars().find(|c| !c.is_ascii_hexdigit()) {
+        return Err(format!("{} contains non-hex character '{}'", what, c));
+    }
+    Ok(value)
+}
+
+/// Expected number of keys to try before finding a match.
+///
+/// Each fixed hex character of the prefix and suffix divides the odds by 16.
+/// A regex pattern cannot be estimated in general and is not accounted for,
+/// so the estimate is a lower bound when `pattern` is set. Errors if the
+/// prefix or suffix is not hex or they don't fit in a public key together.
+pub fn estimate_difficulty(config: &VanityConfig) -> Result<f64, String> {
+    let prefix = normalize_hex("prefix", config.prefix.as_deref())?;
+    let suffix = normalize_hex("suffix", config.suffix.as_deref())?;
+    let fixed = prefix.len() + suffix.len();
+    if fixed > 64 {
+        return Err("prefix and suffix together exceed the 64 hex characters of a public key".to_string());
+    }
+    Ok(16f64.powi(fixed as i32))
+}
+
+/// Search for a key pair whose public key satisfies `config`.
+///
+/// Work is spread over `config.threads` threads. Setting `cancel` to true from
+/// another thread stops the search. `on_progress` is invoked from the calling
+/// thread every `progress_interval_ms` while the search runs.
+///
+/// Returns the first matching `KeyPair` (with no clist), or an error if the
+/// config is invalid, the search was cancelled or `max_attempts` was reached.
+pub fn generate_vanity_key_pair(config: &VanityConfig, cancel: &AtomicBool, mut on_progress: Option<&mut dyn FnMut(&VanityProgress)>) -> Result<KeyPair, String> {
+    let matcher = Matcher::new(config)?;
+    let threads = config.threads.max(1);
+    let expected_attempts = estimate_difficulty(config)?;
+    let attempts = AtomicU64::new(0);
+    let done = AtomicBool::new(false);
+    let found: Mutex<Option<KeyPair>> = Mutex::new(None);
+    let start = Instant::now();
+
+    std::thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| search_worker(&matcher, config.max_attempts, cancel, &done, &attempts, &found));
+        }
+
+        let interval = Duration::from_millis(config.progress_interval_ms.max(1));
+        let tick = interval.min(Duration::from_millis(50));
+        let mut last_report = Instant::now();
+        while !done.load(Ordering::Relaxed) {
+            sleep(tick);
+            if let Some(cb) = on_progress.as_mut() {
+                if last_report.elapsed() >= interval {
+                    last_report = Instant::now();
+                    let tried = attempts.load(Ordering::Relaxed);
+                    let elapsed = start.elapsed();
+                    let rate = tried as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+                    cb(&VanityProgress { attempts: tried, elapsed, rate, expected_attempts });
+                }
+            }
+        }
+    });
+
+    if let Some(kp) = found.into_inner().unwrap_or_else(|e| e.into_inner()) {
+        return Ok(kp);
+    }
+    if cancel.load(Ordering::Relaxed) {
+        return Err("vanity search cancelled".to_string());
+    }
+    Err(format!("Max attempts ({}) reached without a matching key", config.max_attempts))
+}
+
+fn search_worker(matcher: &Matcher, max_attempts: u64, cancel: &AtomicBool, done: &AtomicBool, attempts: &AtomicU64, found: &Mutex<Option<KeyPair>>) {
+    use rand::RngCore;
+    let mut rng = rand::thread_rng();
+    let mut seed = [0u8; 32];
+    while !done.load(Ordering::Relaxed) {
+        if cancel.load(Ordering::Relaxed) {
+            done.store(true, Ordering::Relaxed);
+            return;
+        }
+        let tried = attempts.fetch_add(1, Ordering::Relaxed) + 1;
+        if max_attempts > 0 && tried > max_attempts {
+            done.store(true, Ordering::Relaxed);
+            return;
+        }
+        rng.fill_bytes(&mut seed);
+        let secret_key = SecretKey::from_bytes(&seed).expect("32 bytes, within curve order");
+        let public_key: PublicKey = (&secret_key).into();
+        let public_hex = hex::encode(public_key.as_bytes());
+        if matcher.is_match(&public_hex) {
+            let mut slot = found.lock().unwrap_or_else(|e| e.into_inner());
+            if slot.is_none() {
+                *slot = Some(KeyPair { public_key: public_hex, secret_key: hex::encode(secret_key.as_bytes()), clist: None, scheme: None, addr: None });
+            }
+            done.store(true, Ordering::Relaxed);
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(prefix: &str, suffix: &str) -> VanityConfig {
+        VanityConfig { prefix: Some(prefix.to_string()), suffix: Some(suffix.to_string()), threads: 2, ..VanityConfig::default() }
+    }
+
+    #[test]
+    fn difficulty_counts_fixed_hex_characters() {
+        assert_eq!(estimate_difficulty(&VanityConfig::default()), Ok(1.0));
+        assert_eq!(estimate_difficulty(&config("Ab", "c")), Ok(4096.0));
+        assert!(estimate_difficulty(&config("xy", "")).unwrap_err().contains("non-hex character 'x'"));
+        assert!(estimate_difficulty(&config(&"a".repeat(40), &"b".repeat(25))).is_err());
+    }
+
+    #[test]
+    fn found_keys_match_and_belong_together() {
+        let cfg = VanityConfig { pattern: Some("^[0-9a-f]+$".to_string()), ..config("A", "") };
+        let kp = generate_vanity_key_pair(&cfg, &AtomicBool::new(false), None).unwrap();
+        assert!(kp.public_key.starts_with('a') && kp.public_key.len() == 64, "{}", kp.public_key);
+        let secret = SecretKey::from_bytes(&hex::decode(&kp.secret_key).unwrap()).unwrap();
+        assert_eq!(hex::encode(PublicKey::from(&secret).as_bytes()), kp.public_key);
+    }
+
+    #[test]
+    fn searches_stop_at_max_attempts_or_when_cancelled() {
+        let cfg = VanityConfig { max_attempts: 10, ..config(&"0".repeat(16), "") };
+        assert!(generate_vanity_key_pair(&cfg, &AtomicBool::new(false), None).unwrap_err().contains("Max attempts (10)"));
+        let cfg = config(&"0".repeat(16), "");
+        assert_eq!(generate_vanity_key_pair(&cfg, &AtomicBool::new(true), None).unwrap_err(), "vanity search cancelled");
+        let bad = VanityConfig { pattern: Some("(".to_string()), ..VanityConfig::default() };
+        assert!(generate_vanity_key_pair(&bad, &AtomicBool::new(false), None).unwrap_err().starts_with("invalid pattern"));
+    }
+}
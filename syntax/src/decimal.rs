@@ -0,0 +1,519 @@
+// Decimal module: exact, arbitrary precision decimals and integers rendered the way Pact expects
+use serde::de::{self, Deserializer};
+use serde::ser::{SerializeMap, Serializer};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+use std::str::FromStr;
+
+/// Decimal places supported by `coin` (KDA).
+pub const COIN_PRECISION: u32 = 12;
+
+/// An exact decimal number: `mantissa / 10^scale`.
+///
+/// Parsed from strings rather than floats so amounts like `0.1` stay exact,
+/// and always rendered with a decimal point (`2.0`, never `2`) so Pact types
+/// it as `decimal`, not `integer`. In JSON it uses Pact's `{"decimal": "2.0"}`
+/// encoding.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PactDecimal {
+    negative: bool,
+    // Decimal digits without leading zeros, "0" for zero
+    mantissa: String,
+    scale: u32,
+}
+
+impl PactDecimal {
+    pub fn zero() -> Self {
+        Self { negative: false, mantissa: "0".to_string(), scale: 0 }
+    }
+
+    /// Parse a human-entered amount such as `"12.5"`, `"-0.000001"`, `"1,000.25"` or `"1_000"`.
+    ///
+    /// Grouping separators (`,` and `_`) are accepted in the integer part only.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let s = input.trim();
+        let (negative, s) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (int_part, frac_part) = match s.split_once('.') {
+            Some((i, f)) => (i, f),
+            None => (s, ""),
+        };
+        let int_digits: String = int_part.chars().filter(|c| *c != ',' && *c != '_').collect();
+        if int_digits.is_empty() && frac_part.is_empty() {
+            return Err(format!("not a decimal number: {:?}", input));
+        }
+        // every separator must sit between two digit groups: no "1,,000", ",1" or "1_"
+        if int_part.contains([',', '_']) && int_part.split([',', '_']).any(|group| group.is_empty()) {
+            return Err(format!("misplaced digit separator in {:?}", input));
+        }
+        if !int_digits.chars().all(|c| c.is_ascii_digit()) || !frac_part.chars().all(|c| c.is_ascii_digit()) {
+            return Err(format!("not a decimal number: {:?}", input));
+        }
+        Ok(Self::from_parts(negative, format!("{}{}", int_digits, frac_part), frac_part.len() as u32))
+    }
+
+    /// Convert a float through its shortest exact representation (`0.1` -> `0.1`).
+    ///
+    /// Arithmetic already done in `f64` is not undone: `0.1 + 0.2` arrives as
+    /// `0.30000000000000004`, which `check_precision` then rejects.
+    pub fn from_f64(value: f64) -> Result<Self, String> {
+        if !value.is_finite() {
+            return Err(format!("{} is not a finite number", value));
+        }
+        Self::parse(&format!("{}", value))
+    }
+
+    fn from_parts(negative: bool, digits: String, scale: u32) -> Self {
+        let mut mantissa = digits.trim_start_matches('0').to_string();
+        let mut scale = scale;
+        while scale > 0 && mantissa.ends_with('0') {
+            mantissa.pop();
+            scale -= 1;
+        }
+        if mantissa.is_empty() {
+            return Self::zero();
+        }
+        Self { negative, mantissa, scale }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.mantissa == "0"
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    /// Number of significant decimal places (`1.50` has 1).
+    pub fn places(&self) -> u32 {
+        self.scale
+    }
+
+    /// Fail if the value has more decimal places than `precision`, e.g. `COIN_PRECISION`.
+    pub fn check_precision(&self, precision: u32) -> Result<(), String> {
+        if self.scale > precision {
+            return Err(format!("{} has {} decimal places, more than the allowed {}", self, self.scale, precision));
+        }
+        Ok(())
+    }
+
+    /// Pact literal: `2.0`, `0.000001`, `-3.25`.
+    pub fn to_pact(&self) -> String {
+        let sign = if self.negative { "-" } else { "" };
+        let scale = self.scale as usize;
+        if scale == 0 {
+            return format!("{}{}.0", sign, self.mantissa);
+        }
+        let padded = format!("{:0>width$}", self.mantissa, width = scale + 1);
+        let (int_part, frac_part) = padded.split_at(padded.len() - scale);
+        format!("{}{}.{}", sign, int_part, frac_part)
+    }
+
+    /// Pact JSON encoding for env data and capability args: `{"decimal": "2.0"}`.
+    pub fn to_json(&self) -> Value {
+        json!({"decimal": self.to_pact()})
+    }
+
+    // Mantissa rescaled to `scale` (>= self.scale)
+    fn digits_at(&self, scale: u32) -> String {
+        format!("{}{}", self.mantissa, "0".repeat((scale - self.scale) as usize))
+    }
+}
+
+fn cmp_digits(a: &str, b: &str) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+fn add_digits(a: &str, b: &str) -> String {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let mut out = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0;
+    for i in 0..a.len().max(b.len()) {
+        let da = if i < a.len() { a[a.len() - 1 - i] - b'0' } else { 0 };
+        let db = if i < b.len() { b[b.len() - 1 - i] - b'0' } else { 0 };
+        let sum = da + db + carry;
+        out.push(b'0' + sum % 10);
+        carry = sum / 10;
+    }
+    if carry > 0 {
+        out.push(b'0' + carry);
+    }
+    out.reverse();
+    String::from_utf8(out).unwrap()
+}
+
+// a - b with a >= b
+fn sub_digits(a: &str, b: &str) -> String {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let mut out = Vec::with_capacity(a.len());
+    let mut borrow = 0i8;
+    for i in 0..a.len() {
+        let da = (a[a.len() - 1 - i] - b'0') as i8;
+        let db = if i < b.len() { (b[b.len() - 1 - i] - b'0') as i8 } else { 0 };
+        let mut diff = da - db - borrow;
+        borrow = 0;
+        if diff < 0 {
+            diff += 10;
+            borrow = 1;
+        }
+        out.push(b'0' + diff as u8);
+    }
+    out.reverse();
+    String::from_utf8(out).unwrap()
+}
+
+fn mul_digits(a: &str, b: &str) -> String {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let mut acc = vec![0u32; a.len() + b.len()];
+    for (i, da) in a.iter().rev().enumerate() {
+        for (j, db) in b.iter().rev().enumerate() {
+            acc[i + j] += ((da - b'0') * (db - b'0')) as u32;
+        }
+    }
+    let mut carry = 0;
+    for slot in acc.iter_mut() {
+        let sum = *slot + carry;
+        *slot = sum % 10;
+        carry = sum / 10;
+    }
+    acc.iter().rev().map(|d| char::from(b'0' + *d as u8)).collect()
+}
+
+impl Add for PactDecimal {
+    type Output = PactDecimal;
+
+    fn add(self, rhs: PactDecimal) -> PactDecimal {
+        let scale = self.scale.max(rhs.scale);
+        let (a, b) = (self.digits_at(scale), rhs.digits_at(scale));
+        if self.negative == rhs.negative {
+            return Self::from_parts(self.negative, add_digits(&a, &b), scale);
+        }
+        match cmp_digits(&a, &b) {
+            Ordering::Less => Self::from_parts(rhs.negative, sub_digits(&b, &a), scale),
+            _ => Self::from_parts(self.negative, sub_digits(&a, &b), scale),
+        }
+    }
+}
+
+impl Neg for PactDecimal {
+    type Output = PactDecimal;
+
+    fn neg(self) -> PactDecimal {
+        let negative = !self.negative && !self.is_zero();
+        Self { negative, ..self }
+    }
+}
+
+impl Sub for PactDecimal {
+    type Output = PactDecimal;
+
+    fn sub(self, rhs: PactDecimal) -> PactDecimal {
+        self + (-rhs)
+    }
+}
+
+impl Mul for PactDecimal {
+    type Output = PactDecimal;
+
+    fn mul(self, rhs: PactDecimal) -> PactDecimal {
+        let negative = self.negative != rhs.negative;
+        Self::from_parts(negative, mul_digits(&self.mantissa, &rhs.mantissa), self.scale + rhs.scale)
+    }
+}
+
+impl Ord for PactDecimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (negative, _) => {
+                let scale = self.scale.max(other.scale);
+                let ord = cmp_digits(&self.digits_at(scale), &other.digits_at(scale));
+                if negative { ord.reverse() } else { ord }
+            }
+        }
+    }
+}
+
+impl PartialOrd for PactDecimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for PactDecimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_pact())
+    }
+}
+
+impl FromStr for PactDecimal {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        Self::parse(s)
+    }
+}
+
+/// Fails on NaN and infinities, see `PactDecimal::from_f64`.
+impl TryFrom<f64> for PactDecimal {
+    type Error = String;
+
+    fn try_from(value: f64) -> Result<Self, String> {
+        Self::from_f64(value)
+    }
+}
+
+impl From<i64> for PactDecimal {
+    fn from(value: i64) -> Self {
+        Self::from_parts(value < 0, value.unsigned_abs().to_string(), 0)
+    }
+}
+
+impl From<i32> for PactDecimal {
+    fn from(value: i32) -> Self {
+        Self::from(value as i64)
+    }
+}
+
+impl From<u64> for PactDecimal {
+    fn from(value: u64) -> Self {
+        Self::from_parts(false, value.to_string(), 0)
+    }
+}
+
+impl From<&PactDecimal> for PactDecimal {
+    fn from(value: &PactDecimal) -> Self {
+        value.clone()
+    }
+}
+
+/// An exact integer of any size, like Pact's `integer`: literals such as
+/// `100000000000000000000000` are valid Pact and do not fit an `i64`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PactInteger {
+    negative: bool,
+    // Decimal digits without leading zeros, "0" for zero
+    digits: String,
+}
+
+impl PactInteger {
+    /// Parse an optionally signed run of ASCII digits, such as `"-12"`.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let (negative, digits) = match input.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, input.strip_prefix('+').unwrap_or(input)),
+        };
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(format!("not an integer: {:?}", input));
+        }
+        Ok(Self::from_parts(negative, digits))
+    }
+
+    fn from_parts(negative: bool, digits: &str) -> Self {
+        let digits = match digits.trim_start_matches('0') {
+            "" => "0",
+            trimmed => trimmed,
+        };
+        Self { negative: negative && digits != "0", digits: digits.to_string() }
+    }
+
+    /// The value as an `i64`, if it fits.
+    pub fn to_i64(&self) -> Option<i64> {
+        self.to_string().parse().ok()
+    }
+}
+
+impl fmt::Display for PactInteger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.negative {
+            f.write_str("-")?;
+        }
+        f.write_str(&self.digits)
+    }
+}
+
+impl FromStr for PactInteger {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        Self::parse(s)
+    }
+}
+
+macro_rules! integer_from {
+    ($($t:ty),*) => {
+        $(impl From<$t> for PactInteger {
+            fn from(value: $t) -> Self {
+                Self::from_parts(value < 0, &value.unsigned_abs().to_string())
+            }
+        })*
+    };
+}
+
+integer_from!(i8, i16, i32, i64, i128, isize);
+
+macro_rules! unsigned_integer_from {
+    ($($t:ty),*) => {
+        $(impl From<$t> for PactInteger {
+            fn from(value: $t) -> Self {
+                Self::from_parts(false, &value.to_string())
+            }
+        })*
+    };
+}
+
+unsigned_integer_from!(u8, u16, u32, u64, u128, usize);
+
+/// Anything accepted as an amount: `PactDecimal`s and integers convert
+/// exactly, strings are parsed with `PactDecimal::parse` and floats go through
+/// `PactDecimal::from_f64`, so NaN, infinities and malformed text are errors.
+pub trait IntoPactDecimal {
+    fn into_pact_decimal(self) -> Result<PactDecimal, String>;
+}
+
+impl IntoPactDecimal for PactDecimal {
+    fn into_pact_decimal(self) -> Result<PactDecimal, String> {
+        Ok(self)
+    }
+}
+
+impl IntoPactDecimal for &PactDecimal {
+    fn into_pact_decimal(self) -> Result<PactDecimal, String> {
+        Ok(self.clone())
+    }
+}
+
+impl IntoPactDecimal for f64 {
+    fn into_pact_decimal(self) -> Result<PactDecimal, String> {
+        PactDecimal::from_f64(self)
+    }
+}
+
+impl IntoPactDecimal for &str {
+    fn into_pact_decimal(self) -> Result<PactDecimal, String> {
+        PactDecimal::parse(self)
+    }
+}
+
+impl IntoPactDecimal for i32 {
+    fn into_pact_decimal(self) -> Result<PactDecimal, String> {
+        Ok(PactDecimal::from(self))
+    }
+}
+
+impl IntoPactDecimal for i64 {
+    fn into_pact_decimal(self) -> Result<PactDecimal, String> {
+        Ok(PactDecimal::from(self))
+    }
+}
+
+impl IntoPactDecimal for u64 {
+    fn into_pact_decimal(self) -> Result<PactDecimal, String> {
+        Ok(PactDecimal::from(self))
+    }
+}
+
+impl Serialize for PactDecimal {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry("decimal", &self.to_pact())?;
+        map.end()
+    }
+}
+
+/// Accepts `{"decimal": "1.5"}`, `"1.5"` or a JSON number.
+impl<'de> Deserialize<'de> for PactDecimal {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        if let Some(f) = value.as_f64().filter(|_| value.is_f64()) {
+            return PactDecimal::from_f64(f).map_err(de::Error::custom);
+        }
+        let text = match &value {
+            Value::Object(obj) => obj.get("decimal").and_then(|d| d.as_str()).map(|s| s.to_string()),
+            Value::String(s) => Some(s.clone()),
+            Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        };
+        let text = text.ok_or_else(|| de::Error::custom(format!("expected a Pact decimal, got {}", value)))?;
+        PactDecimal::parse(&text).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(s: &str) -> PactDecimal {
+        PactDecimal::parse(s).unwrap()
+    }
+
+    #[test]
+    fn parsing_and_rendering() {
+        for (input, pact) in [("12.5", "12.5"), ("1,000.25", "1000.25"), ("1_000", "1000.0"), ("-0.000001", "-0.000001"), ("+2", "2.0"), ("1.50", "1.5"), ("-0.0", "0.0"), (".5", "0.5"), ("007.", "7.0")] {
+            assert_eq!(d(input).to_pact(), pact, "{}", input);
+        }
+        for input in ["", ".", "1,,000", ",1", "1_", "1.2.3", "1e5", "0x10", "1.0,0"] {
+            assert!(PactDecimal::parse(input).is_err(), "{}", input);
+        }
+        assert_eq!(d("1.50").places(), 1);
+        assert!(d("-0").is_zero() && !d("-0").is_negative());
+    }
+
+    #[test]
+    fn floats_convert_through_their_shortest_form() {
+        assert_eq!(PactDecimal::from_f64(0.1), Ok(d("0.1")));
+        assert_eq!(PactDecimal::from_f64(0.0000001), Ok(d("0.0000001")));
+        assert_eq!(PactDecimal::from_f64(0.1 + 0.2), Ok(d("0.30000000000000004")));
+        assert!(PactDecimal::from_f64(f64::NAN).is_err() && PactDecimal::try_from(f64::INFINITY).is_err());
+        assert!("1,,000".into_pact_decimal().is_err());
+        assert_eq!(7u64.into_pact_decimal(), Ok(d("7")));
+    }
+
+    #[test]
+    fn arithmetic_and_ordering_are_exact() {
+        assert_eq!(d("0.1") + d("0.2"), d("0.3"));
+        assert_eq!(d("1") - d("1.25"), d("-0.25"));
+        assert_eq!(d("-1.5") + d("1.5"), PactDecimal::zero());
+        assert_eq!(d("-1.5") * d("0.2"), d("-0.3"));
+        assert_eq!(-PactDecimal::zero(), PactDecimal::zero());
+        let mut values = vec![d("2"), d("-1.5"), d("0.25"), d("-10"), d("0.3")];
+        values.sort();
+        assert_eq!(values, [d("-10"), d("-1.5"), d("0.25"), d("0.3"), d("2")]);
+    }
+
+    #[test]
+    fn precision_checks() {
+        assert_eq!(d("0.000000000001").check_precision(COIN_PRECISION), Ok(()));
+        let err = d("0.0000000000001").check_precision(COIN_PRECISION).unwrap_err();
+        assert_eq!(err, "0.0000000000001 has 13 decimal places, more than the allowed 12");
+    }
+
+    #[test]
+    fn json_encodings() {
+        assert_eq!(serde_json::to_value(d("2")).unwrap(), json!({"decimal": "2.0"}));
+        for value in [json!({"decimal": "2.50"}), json!("2.5"), json!(2.5)] {
+            assert_eq!(serde_json::from_value::<PactDecimal>(value).unwrap(), d("2.5"));
+        }
+        assert_eq!(serde_json::from_value::<PactDecimal>(json!(3)).unwrap(), d("3"));
+        assert!(serde_json::from_value::<PactDecimal>(json!(true)).is_err());
+    }
+
+    #[test]
+    fn integers_of_any_size() {
+        let big = PactInteger::parse("-000100000000000000000000000").unwrap();
+        assert_eq!(big.to_string(), "-100000000000000000000000");
+        assert_eq!(big.to_i64(), None);
+        assert_eq!(PactInteger::parse("-0").unwrap(), PactInteger::from(0u8));
+        assert_eq!(PactInteger::from(i128::MIN).to_string(), i128::MIN.to_string());
+        assert_eq!(PactInteger::from(u64::MAX).to_string(), u64::MAX.to_string());
+        for input in ["", "-", "1.0", "+-1", " 1"] {
+            assert!(PactInteger::parse(input).is_err(), "{:?}", input);
+        }
+    }
+}
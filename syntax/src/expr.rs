@@ -0,0 +1,568 @@
+// Expr module: Pact expression AST and a renderer that always escapes literals
+use crate::decimal::{PactDecimal, PactInteger};
+use chrono::{DateTime, Timelike, Utc};
+use std::fmt;
+
+/// A Pact expression.
+///
+/// Build code from this instead of formatting strings: string literals are
+/// always escaped when rendered, so user input such as an account name can
+/// never turn into code, and names are checked to be valid identifiers.
+///
+/// The constructors check names up front, and the parser only accepts names
+/// that render. Names put directly into a variant (`PactExpr::Symbol(..)`)
+/// are not checked by `Display`, which writes them as they are; use `render`,
+/// which returns an error for them, wherever such a name may come from input.
+///
+/// ```text
+/// let code = PactExpr::call("coin.transfer", vec![
+///     PactExpr::string(&sender), PactExpr::string(&receiver), PactExpr::decimal(amount),
+/// ])?;
+/// code.to_string() // (coin.transfer "k:.." "k:.." 1.5)
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum PactExpr {
+    /// Bare name: a variable, builtin or keyword such as `keys-all`.
+    Symbol(String),
+    /// `module.name` or `namespace.module.name`.
+    Qualified { namespace: Option<String>, module: String, name: String },
+    String(String),
+    Integer(PactInteger),
+    Decimal(PactDecimal),
+    Bool(bool),
+    /// Rendered as `(time "2024-01-01T00:00:00Z")`, or with `parse-time` and
+    /// microseconds when it has fractional seconds; Pact has no time literal.
+    Time(DateTime<Utc>),
+    List(Vec<PactExpr>),
+    Object(Vec<(String, PactExpr)>),
+    /// Function application `(f arg ...)`.
+    App(Box<PactExpr>, Vec<PactExpr>),
+    /// `(let ((name value) ...) body ...)`.
+    Let(Vec<(String, PactExpr)>, Vec<PactExpr>),
+    /// `(let* ((name value) ...) body ...)`.
+    LetStar(Vec<(String, PactExpr)>, Vec<PactExpr>),
+    /// Name with a type annotation: `amount:decimal`, `acct:object{account}`.
+    Typed { name: String, ty: String },
+    /// Metadata such as `@doc "..."`, `@model [...]`, `@event` or `@managed amount MGR`.
+    Meta(String, Vec<PactExpr>),
+    /// Binding object of `with-read`/`bind`: `{"balance" := bal}`.
+    Bindings(Vec<(String, PactExpr)>),
+    /// `defun`, `defcap`, `defpact`, `defschema` or `deftable`.
+    Def(Box<PactDef>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DefKind {
+    Defun,
+    Defcap,
+    Defpact,
+    Defschema,
+    Deftable,
+}
+
+impl DefKind {
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            DefKind::Defun => "defun",
+            DefKind::Defcap => "defcap",
+            DefKind::Defpact => "defpact",
+            DefKind::Defschema => "defschema",
+            DefKind::Deftable => "deftable",
+        }
+    }
+
+    pub fn from_keyword(keyword: &str) -> Option<Self> {
+        match keyword {
+            "defun" => Some(DefKind::Defun),
+            "defcap" => Some(DefKind::Defcap),
+            "defpact" => Some(DefKind::Defpact),
+            "defschema" => Some(DefKind::Defschema),
+            "deftable" => Some(DefKind::Deftable),
+            _ => None,
+        }
+    }
+}
+
+/// A definition. `params` is None for `defschema` and `deftable`, whose
+/// fields and metadata are in `body`; metadata of other definitions is in
+/// `body` too, in source order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PactDef {
+    pub kind: DefKind,
+    pub name: String,
+    pub ty: Option<String>,
+    pub params: Option<Vec<(String, Option<String>)>>,
+    pub body: Vec<PactExpr>,
+}
+
+impl PactExpr {
+    /// Symbol `name`; fails if `name` is not a valid Pact identifier.
+    pub fn symbol(name: &str) -> Result<Self, String> {
+        check_ident(name)?;
+        Ok(PactExpr::Symbol(name.to_string()))
+    }
+
+    /// Parse `name`, `module.name` or `namespace.module.name`, checking every part.
+    pub fn qualified(name: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = name.split('.').collect();
+        for part in &parts {
+            check_ident(part)?;
+        }
+        match parts.as_slice() {
+            [name] => Ok(PactExpr::Symbol(name.to_string())),
+            [module, name] => Ok(PactExpr::Qualified { namespace: None, module: module.to_string(), name: name.to_string() }),
+            [ns, module, name] => Ok(PactExpr::Qualified { namespace: Some(ns.to_string()), module: module.to_string(), name: name.to_string() }),
+            _ => Err(format!("too many parts in qualified name {:?}", name)),
+        }
+    }
+
+    pub fn string(s: &str) -> Self {
+        PactExpr::String(s.to_string())
+    }
+
+    pub fn integer(i: impl Into<PactInteger>) -> Self {
+        PactExpr::Integer(i.into())
+    }
+
+    pub fn decimal(d: impl Into<PactDecimal>) -> Self {
+        PactExpr::Decimal(d.into())
+    }
+
+    pub fn bool(b: bool) -> Self {
+        PactExpr::Bool(b)
+    }
+
+    pub fn time(t: DateTime<Utc>) -> Self {
+        PactExpr::Time(t)
+    }
+
+    pub fn list(items: Vec<PactExpr>) -> Self {
+        PactExpr::List(items)
+    }
+
+    pub fn object(fields: Vec<(&str, PactExpr)>) -> Self {
+        PactExpr::Object(fields.into_iter().map(|(k, v)| (k.to_string(), v)).collect())
+    }
+
+    pub fn app(head: PactExpr, args: Vec<PactExpr>) -> Self {
+        PactExpr::App(Box::new(head), args)
+    }
+
+    /// Application of the function named `name` (see `qualified`).
+    pub fn call(name: &str, args: Vec<PactExpr>) -> Result<Self, String> {
+        Ok(Self::app(Self::qualified(name)?, args))
+    }
+
+    pub fn let_in(bindings: Vec<(&str, PactExpr)>, body: Vec<PactExpr>) -> Result<Self, String> {
+        for (name, _) in &bindings {
+            check_ident(name)?;
+        }
+        Ok(PactExpr::Let(bindings.into_iter().map(|(k, v)| (k.to_string(), v)).collect(), body))
+    }
+
+    /// `(read-keyset "name")`
+    pub fn read_keyset(name: &str) -> Self {
+        Self::app(PactExpr::Symbol("read-keyset".to_string()), vec![Self::string(name)])
+    }
+
+    /// `(read-msg "name")`
+    pub fn read_msg(name: &str) -> Self {
+        Self::app(PactExpr::Symbol("read-msg".to_string()), vec![Self::string(name)])
+    }
+
+    /// Pact source for this expression, or an error where `validate` finds one.
+    pub fn render(&self) -> Result<String, String> {
+        self.validate()?;
+        Ok(self.to_string())
+    }
+
+    /// Check every name in this expression (symbols, qualified names, binding
+    /// and parameter names, types and metadata names), and that times need no
+    /// more than the microseconds Pact keeps.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut result = Ok(());
+        self.walk(&mut |e| {
+            if result.is_ok() {
+                result = e.check_node();
+            }
+        });
+        result
+    }
+
+    // Names and times held by this node itself, not by its children
+    fn check_node(&self) -> Result<(), String> {
+        match self {
+            PactExpr::Time(t) if t.nanosecond() % 1000 != 0 => Err(format!("time {} is more precise than Pact's microseconds", t.to_rfc3339())),
+            PactExpr::Symbol(s) => check_ident(s.strip_prefix('\'').unwrap_or(s)),
+            PactExpr::Qualified { namespace, module, name } => {
+                namespace.iter().chain([module, name]).try_for_each(|part| check_ident(part))
+            }
+            PactExpr::Let(bindings, _) | PactExpr::LetStar(bindings, _) => bindings.iter().try_for_each(|(name, _)| match name.split_once(':') {
+                Some((name, ty)) => check_ident(name).and_then(|_| check_type(ty)),
+                None => check_ident(name),
+            }),
+            PactExpr::Typed { name, ty } => check_ident(name).and_then(|_| check_type(ty)),
+            PactExpr::Meta(name, _) => check_ident(name),
+            PactExpr::Def(def) => {
+                check_ident(&def.name)?;
+                def.ty.iter().try_for_each(|ty| check_type(ty))?;
+                def.params.iter().flatten().try_for_each(|(name, ty)| check_ident(name).and_then(|_| ty.iter().try_for_each(|ty| check_type(ty))))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Direct sub-expressions, in source order.
+    pub fn children(&self) -> Vec<&PactExpr> {
+        match self {
+            PactExpr::List(items) => items.iter().collect(),
+            PactExpr::Object(fields) | PactExpr::Bindings(fields) => fields.iter().map(|(_, v)| v).collect(),
+            PactExpr::App(head, args) => std::iter::once(head.as_ref()).chain(args).collect(),
+            PactExpr::Let(bindings, body) | PactExpr::LetStar(bindings, body) => bindings.iter().map(|(_, v)| v).chain(body).collect(),
+            PactExpr::Meta(_, values) => values.iter().collect(),
+            PactExpr::Def(def) => def.body.iter().collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Visit this expression and all sub-expressions, depth first.
+    pub fn walk(&self, f: &mut impl FnMut(&PactExpr)) {
+        f(self);
+        for child in self.children() {
+            child.walk(f);
+        }
+    }
+
+    /// Mutable counterpart of `children`, e.g. to rewrite names in place.
+    pub fn children_mut(&mut self) -> Vec<&mut PactExpr> {
+        match self {
+            PactExpr::List(items) => items.iter_mut().collect(),
+            PactExpr::Object(fields) | PactExpr::Bindings(fields) => fields.iter_mut().map(|(_, v)| v).collect(),
+            PactExpr::App(head, args) => std::iter::once(head.as_mut()).chain(args.iter_mut()).collect(),
+            PactExpr::Let(bindings, body) | PactExpr::LetStar(bindings, body) => bindings.iter_mut().map(|(_, v)| v).chain(body.iter_mut()).collect(),
+            PactExpr::Meta(_, values) => values.iter_mut().collect(),
+            PactExpr::Def(def) => def.body.iter_mut().collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Mutable counterpart of `walk`.
+    pub fn walk_mut(&mut self, f: &mut impl FnMut(&mut PactExpr)) {
+        f(self);
+        for child in self.children_mut() {
+            child.walk_mut(f);
+        }
+    }
+}
+
+impl fmt::Display for PactExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PactExpr::Symbol(s) => f.write_str(s),
+            PactExpr::Qualified { namespace: Some(ns), module, name } => write!(f, "{}.{}.{}", ns, module, name),
+            PactExpr::Qualified { namespace: None, module, name } => write!(f, "{}.{}", module, name),
+            PactExpr::String(s) => f.write_str(&escape_string(s)),
+            PactExpr::Integer(i) => write!(f, "{}", i),
+            PactExpr::Decimal(d) => f.write_str(&d.to_pact()),
+            PactExpr::Bool(b) => write!(f, "{}", b),
+            PactExpr::Time(t) if t.nanosecond() == 0 => write!(f, "(time {})", escape_string(&t.format("%Y-%m-%dT%H:%M:%SZ").to_string())),
+            PactExpr::Time(t) => write!(f, "(parse-time \"%Y-%m-%dT%H:%M:%S.%vZ\" {})", escape_string(&t.format("%Y-%m-%dT%H:%M:%S%.6fZ").to_string())),
+            PactExpr::List(items) => {
+                f.write_str("[")?;
+                write_separated(f, items)?;
+                f.write_str("]")
+            }
+            PactExpr::Object(fields) => {
+                f.write_str("{")?;
+                for (i, (k, v)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}: {}", escape_string(k), v)?;
+                }
+                f.write_str("}")
+            }
+            PactExpr::App(head, args) => {
+                write!(f, "({}", head)?;
+                for arg in args {
+                    write!(f, " {}", arg)?;
+                }
+                f.write_str(")")
+            }
+            PactExpr::Let(bindings, body) | PactExpr::LetStar(bindings, body) => {
+                let keyword = if matches!(self, PactExpr::Let(..)) { "let" } else { "let*" };
+                write!(f, "({} (", keyword)?;
+                for (i, (name, value)) in bindings.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(" ")?;
+                    }
+                    write!(f, "({} {})", name, value)?;
+                }
+                f.write_str(")")?;
+                for expr in body {
+                    write!(f, " {}", expr)?;
+                }
+                f.write_str(")")
+            }
+            PactExpr::Typed { name, ty } => write!(f, "{}:{}", name, ty),
+            PactExpr::Meta(name, values) => {
+                write!(f, "@{}", name)?;
+                for value in values {
+                    write!(f, " {}", value)?;
+                }
+                Ok(())
+            }
+            PactExpr::Bindings(fields) => {
+                f.write_str("{")?;
+                for (i, (k, v)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{} := {}", escape_string(k), v)?;
+                }
+                f.write_str("}")
+            }
+            PactExpr::Def(def) => {
+                write!(f, "({} {}", def.kind.keyword(), def.name)?;
+                if let Some(ty) = &def.ty {
+                    write!(f, ":{}", ty)?;
+                }
+                if let Some(params) = &def.params {
+                    f.write_str(" (")?;
+                    for (i, (name, ty)) in params.iter().enumerate() {
+                        if i > 0 {
+                            f.write_str(" ")?;
+                        }
+                        match ty {
+                            Some(ty) => write!(f, "{}:{}", name, ty)?,
+                            None => f.write_str(name)?,
+                        }
+                    }
+                    f.write_str(")")?;
+                }
+                for expr in &def.body {
+                    write!(f, " {}", expr)?;
+                }
+                f.write_str(")")
+            }
+        }
+    }
+}
+
+impl From<&str> for PactExpr {
+    fn from(s: &str) -> Self {
+        PactExpr::string(s)
+    }
+}
+
+impl From<i64> for PactExpr {
+    fn from(i: i64) -> Self {
+        PactExpr::Integer(i.into())
+    }
+}
+
+impl From<PactInteger> for PactExpr {
+    fn from(i: PactInteger) -> Self {
+        PactExpr::Integer(i)
+    }
+}
+
+impl From<bool> for PactExpr {
+    fn from(b: bool) -> Self {
+        PactExpr::Bool(b)
+    }
+}
+
+impl From<PactDecimal> for PactExpr {
+    fn from(d: PactDecimal) -> Self {
+        PactExpr::Decimal(d)
+    }
+}
+
+/// A Rust value as a Pact expression, for interpolation into code by the
+/// `pact!` macro: strings become escaped string literals, integers and
+/// decimals number literals, slices and vectors lists. Floats are not
+/// accepted, as they may be NaN or inexact; convert with `PactDecimal::try_from`.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` cannot be written as Pact code",
+    note = "floats are not accepted: convert with `PactDecimal::try_from(value)?`"
+)]
+pub trait ToPact {
+    fn to_pact_expr(&self) -> PactExpr;
+}
+
+impl ToPact for PactExpr {
+    fn to_pact_expr(&self) -> PactExpr {
+        self.clone()
+    }
+}
+
+impl ToPact for str {
+    fn to_pact_expr(&self) -> PactExpr {
+        PactExpr::string(self)
+    }
+}
+
+impl ToPact for String {
+    fn to_pact_expr(&self) -> PactExpr {
+        PactExpr::string(self)
+    }
+}
+
+impl ToPact for bool {
+    fn to_pact_expr(&self) -> PactExpr {
+        PactExpr::Bool(*self)
+    }
+}
+
+impl ToPact for PactInteger {
+    fn to_pact_expr(&self) -> PactExpr {
+        PactExpr::Integer(self.clone())
+    }
+}
+
+impl ToPact for PactDecimal {
+    fn to_pact_expr(&self) -> PactExpr {
+        PactExpr::Decimal(self.clone())
+    }
+}
+
+impl ToPact for DateTime<Utc> {
+    fn to_pact_expr(&self) -> PactExpr {
+        PactExpr::Time(*self)
+    }
+}
+
+macro_rules! integer_to_pact {
+    ($($t:ty),*) => {
+        $(impl ToPact for $t {
+            fn to_pact_expr(&self) -> PactExpr {
+                PactExpr::Integer(PactInteger::from(*self))
+            }
+        })*
+    };
+}
+
+// exact for every width, as `PactInteger` has no size limit
+integer_to_pact!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+impl<T: ToPact> ToPact for [T] {
+    fn to_pact_expr(&self) -> PactExpr {
+        PactExpr::List(self.iter().map(ToPact::to_pact_expr).collect())
+    }
+}
+
+impl<T: ToPact> ToPact for Vec<T> {
+    fn to_pact_expr(&self) -> PactExpr {
+        self.as_slice().to_pact_expr()
+    }
+}
+
+impl<T: ToPact + ?Sized> ToPact for &T {
+    fn to_pact_expr(&self) -> PactExpr {
+        (**self).to_pact_expr()
+    }
+}
+
+fn write_separated(f: &mut fmt::Formatter<'_>, items: &[PactExpr]) -> fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            f.write_str(" ")?;
+        }
+        write!(f, "{}", item)?;
+    }
+    Ok(())
+}
+
+/// Quote `s` as a Pact string literal, escaping backslashes, quotes and control characters.
+///
+/// Control characters other than `\n`, `\t` and `\r` become decimal escapes (`\1`), followed by the
+/// empty escape `\&` when a digit comes next, so `"\u{1}2"` is `"\1\&2"` and not `"\12"`.
+pub fn escape_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c if c.is_control() => {
+                out.push_str(&format!("\\{}", c as u32));
+                if chars.peek().is_some_and(|next| next.is_ascii_digit()) {
+                    out.push_str("\\&");
+                }
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Characters allowed in Pact identifiers besides ASCII letters and digits.
+const IDENT_SPECIAL: &str = "%#+-_&$@<>=^?*!|/~";
+
+pub(crate) fn check_ident(name: &str) -> Result<(), String> {
+    let mut chars = name.chars();
+    let valid_first = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || IDENT_SPECIAL.contains(c));
+    if !valid_first || !chars.all(|c| c.is_ascii_alphanumeric() || IDENT_SPECIAL.contains(c)) {
+        return Err(format!("invalid Pact identifier {:?}", name));
+    }
+    Ok(())
+}
+
+// `decimal`, `[string]`, `object{account}`, `module{ns.fungible-v2}`: identifiers, dots and balanced brackets
+pub(crate) fn check_type(ty: &str) -> Result<(), String> {
+    let mut open = Vec::new();
+    for c in ty.chars() {
+        match c {
+            '[' | '{' => open.push(c),
+            ']' if open.pop() == Some('[') => {}
+            '}' if open.pop() == Some('{') => {}
+            c if c.is_ascii_alphanumeric() || c == '.' || IDENT_SPECIAL.contains(c) => {}
+            _ => return Err(format!("invalid Pact type {:?}", ty)),
+        }
+    }
+    if ty.is_empty() || !open.is_empty() {
+        return Err(format!("invalid Pact type {:?}", ty));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numeric_escape_is_terminated_before_a_digit() {
+        assert_eq!(escape_string("\u{1}2"), r#""\1\&2""#);
+        assert_eq!(escape_string("\u{1}x"), r#""\1x""#);
+        let parsed: PactExpr = escape_string("\u{1}2\u{7f}0").parse().unwrap();
+        assert_eq!(parsed, PactExpr::string("\u{1}2\u{7f}0"));
+    }
+
+    #[test]
+    fn invalid_names_in_variants_do_not_render() {
+        let injected = PactExpr::app(PactExpr::Symbol("f) (coin.transfer".to_string()), vec![]);
+        assert!(injected.validate().is_err());
+        assert!(injected.render().is_err());
+        let typed = PactExpr::Typed { name: "x".to_string(), ty: "decimal) (drop".to_string() };
+        assert!(typed.validate().is_err());
+        let ok = PactExpr::app(PactExpr::Symbol("'ks".to_string()), vec![PactExpr::Typed { name: "acct".to_string(), ty: "object{account}".to_string() }]);
+        assert_eq!(ok.validate(), Ok(()));
+        assert_eq!(ok.render(), Ok("('ks acct:object{account})".to_string()));
+    }
+
+    #[test]
+    fn times_keep_their_fractional_seconds() {
+        let whole = DateTime::parse_from_rfc3339("2024-01-02T03:04:05Z").unwrap().with_timezone(&Utc);
+        assert_eq!(PactExpr::time(whole).render(), Ok(r#"(time "2024-01-02T03:04:05Z")"#.to_string()));
+        let micros = DateTime::parse_from_rfc3339("2024-01-02T03:04:05.120Z").unwrap().with_timezone(&Utc);
+        assert_eq!(PactExpr::time(micros).render(), Ok(r#"(parse-time "%Y-%m-%dT%H:%M:%S.%vZ" "2024-01-02T03:04:05.120000Z")"#.to_string()));
+        let nanos = DateTime::parse_from_rfc3339("2024-01-02T03:04:05.000000001Z").unwrap().with_timezone(&Utc);
+        assert!(PactExpr::time(nanos).render().is_err());
+    }
+}
@@ -0,0 +1,6 @@
+//! Pact syntax shared by `rust_pact` and its `pact!` macro: exact decimals,
+//! the expression AST and the source parser. Use them through `rust_pact`.
+
+pub mod decimal;
+pub mod expr;
+pub mod parser;
@@ -0,0 +1,759 @@
+// Parser module: Pact source to the `PactExpr` AST, with source spans
+use crate::decimal::{PactDecimal, PactInteger};
+use crate::expr::{check_ident, check_type, DefKind, PactDef, PactExpr};
+use std::fmt;
+use std::str::FromStr;
+
+/// Location of a piece of source: byte offsets `start..end` and the 1-based
+/// line and column (in characters) of both ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+impl Span {
+    /// The source text this span covers.
+    pub fn text<'a>(&self, src: &'a str) -> &'a str {
+        &src[self.start..self.end]
+    }
+
+    fn to(self, other: Span) -> Span {
+        Span { end: other.end, end_line: other.end_line, end_column: other.end_column, ..self }
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// A parsed expression with its span. `children` holds the spans of the
+/// sub-expressions, one per entry of `expr.children()` and in the same order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spanned {
+    pub expr: PactExpr,
+    pub span: Span,
+    pub children: Vec<Spanned>,
+}
+
+impl Spanned {
+    /// Visit this node and all nodes below it, depth first.
+    pub fn walk(&self, f: &mut impl FnMut(&Spanned)) {
+        f(self);
+        for child in &self.children {
+            child.walk(f);
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub message: String,
+    pub span: Span,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.span, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Deepest nesting of lists, objects, applications and definitions `parse`
+/// accepts; deeper input is a `ParseError` instead of a stack overflow.
+pub const MAX_DEPTH: usize = 256;
+
+/// Parse Pact source into its top-level expressions.
+///
+/// Supports comments, strings with escapes, integers, decimals, booleans,
+/// lists, objects and binding objects (`{"balance" := bal}`), typed names
+/// (`amount:decimal`), metadata (`@doc`, `@model`, `@event`, `@managed`),
+/// `let`/`let*` and `defun`/`defcap`/`defpact`/`defschema`/`deftable`.
+/// Everything else, `module` included, is an application.
+///
+/// ```text
+/// let forms = parse("(coin.transfer \"alice\" \"bob\" 1.0)")?;
+/// let mut calls = vec![];
+/// forms[0].expr.walk(&mut |e| if let PactExpr::App(head, _) = e { calls.push(head.to_string()) });
+/// // calls == ["coin.transfer"], forms[0].children[3].span.text(src) == "1.0"
+/// ```
+pub fn parse(src: &str) -> Result<Vec<Spanned>, ParseError> {
+    parse_with_comments(src).map(|(forms, _)| forms)
+}
+
+/// A `;` comment; `text` includes the semicolons.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comment {
+    pub text: String,
+    pub span: Span,
+}
+
+/// `parse`, also returning the comments the AST leaves out, in source order.
+pub fn parse_with_comments(src: &str) -> Result<(Vec<Spanned>, Vec<Comment>), ParseError> {
+    let mut lexer = Lexer::new(src);
+    let tokens = lexer.tokens()?;
+    let mut parser = Parser { tokens, pos: 0, depth: 0 };
+    let mut forms = Vec::new();
+    while !parser.at_end() {
+        forms.push(parser.form()?);
+    }
+    Ok((forms, lexer.comments))
+}
+
+/// Parse source that holds exactly one expression.
+pub fn parse_expr(src: &str) -> Result<Spanned, ParseError> {
+    let mut forms = parse(src)?;
+    match forms.len() {
+        1 => Ok(forms.remove(0)),
+        0 => Err(ParseError { message: "no expression".to_string(), span: Span { line: 1, column: 1, end_line: 1, end_column: 1, ..Span::default() } }),
+        _ => Err(ParseError { message: "more than one expression".to_string(), span: forms[1].span }),
+    }
+}
+
+impl FromStr for PactExpr {
+    type Err = ParseError;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        parse_expr(src).map(|s| s.expr)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    LBrace,
+    RBrace,
+    Comma,
+    Colon,
+    Bind,
+    Str(String),
+    Int(PactInteger),
+    Dec(PactDecimal),
+    Word(String),
+    Typed(String, String),
+    Meta(String),
+    Eof,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::LParen => "'('".to_string(),
+            Token::RParen => "')'".to_string(),
+            Token::LBracket => "'['".to_string(),
+            Token::RBracket => "']'".to_string(),
+            Token::LBrace => "'{'".to_string(),
+            Token::RBrace => "'}'".to_string(),
+            Token::Comma => "','".to_string(),
+            Token::Colon => "':'".to_string(),
+            Token::Bind => "':='".to_string(),
+            Token::Str(_) => "string".to_string(),
+            Token::Int(_) | Token::Dec(_) => "number".to_string(),
+            Token::Word(w) => format!("{:?}", w),
+            Token::Typed(n, t) => format!("\"{}:{}\"", n, t),
+            Token::Meta(m) => format!("@{}", m),
+            Token::Eof => "end of input".to_string(),
+        }
+    }
+}
+
+/// Characters allowed in names besides ASCII letters and digits; `.` joins qualified names.
+const WORD_SPECIAL: &str = "%#+-_&$@<>=^?*!|/~.'";
+
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || WORD_SPECIAL.contains(c)
+}
+
+struct Lexer<'a> {
+    src: &'a str,
+    pos: usize,
+    line: usize,
+    column: usize,
+    comments: Vec<Comment>,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(src: &'a str) -> Self {
+        Lexer { src, pos: 0, line: 1, column: 1, comments: Vec::new() }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn peek_second(&self) -> Option<char> {
+        self.src[self.pos..].chars().nth(1)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn mark(&self) -> Span {
+        Span { start: self.pos, end: self.pos, line: self.line, column: self.column, end_line: self.line, end_column: self.column }
+    }
+
+    fn close(&self, start: Span) -> Span {
+        Span { end: self.pos, end_line: self.line, end_column: self.column, ..start }
+    }
+
+    fn error<T>(&self, start: Span, message: String) -> Result<T, ParseError> {
+        Err(ParseError { message, span: self.close(start) })
+    }
+
+    fn tokens(&mut self) -> Result<Vec<(Token, Span)>, ParseError> {
+        let mut tokens = Vec::new();
+        loop {
+            self.skip_trivia();
+            let start = self.mark();
+            let Some(c) = self.peek() else {
+                tokens.push((Token::Eof, start));
+                return Ok(tokens);
+            };
+            let token = match c {
+                '(' | ')' | '[' | ']' | '{' | '}' | ',' => {
+                    self.bump();
+                    match c {
+                        '(' => Token::LParen,
+                        ')' => Token::RParen,
+                        '[' => Token::LBracket,
+                        ']' => Token::RBracket,
+                        '{' => Token::LBrace,
+                        '}' => Token::RBrace,
+                        _ => Token::Comma,
+                    }
+                }
+                ':' => {
+                    self.bump();
+                    if self.peek() == Some('=') {
+                        self.bump();
+                        Token::Bind
+                    } else {
+                        Token::Colon
+                    }
+                }
+                '"' => self.string(start)?,
+                '@' => {
+                    self.bump();
+                    let name = self.word();
+                    if name.is_empty() {
+                        return self.error(start, "expected a metadata name after '@'".to_string());
+                    }
+                    Token::Meta(name)
+                }
+                c if c.is_ascii_digit() || ((c == '-' || c == '+') && self.peek_second().is_some_and(|d| d.is_ascii_digit())) => {
+                    let text = self.word();
+                    number(&text).ok_or_else(|| ParseError { message: format!("invalid number {:?}", text), span: self.close(start) })?
+                }
+                c if is_word_char(c) => {
+                    let name = self.word();
+                    if self.peek() == Some(':') && self.peek_second() != Some('=') {
+                        self.bump();
+                        let ty = self.type_annotation();
+                        if ty.is_empty() {
+                            return self.error(start, format!("expected a type after \"{}:\"", name));
+                        }
+                        Token::Typed(name, ty)
+                    } else {
+                        Token::Word(name)
+                    }
+                }
+                c => {
+                    self.bump();
+                    return self.error(start, format!("unexpected character {:?}", c));
+                }
+            };
+            tokens.push((token, self.close(start)));
+        }
+    }
+
+    fn skip_trivia(&mut self) {
+        while let Some(c) = self.peek() {
+            if c == ';' {
+                let start = self.mark();
+                while self.peek().is_some_and(|c| c != '\n') {
+                    self.bump();
+                }
+                let span = self.close(start);
+                self.comments.push(Comment { text: span.text(self.src).trim_end().to_string(), span });
+            } else if c.is_whitespace() {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn word(&mut self) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(is_word_char) {
+            self.bump();
+        }
+        self.src[start..self.pos].to_string()
+    }
+
+    // `string`, `[decimal]`, `object{account}`, `{account}`, `module{fungible-v2}`
+    fn type_annotation(&mut self) -> String {
+        let start = self.pos;
+        let mut depth = 0usize;
+        while let Some(c) = self.peek() {
+            match c {
+                '[' | '{' => depth += 1,
+                ']' | '}' if depth > 0 => depth -= 1,
+                c if depth == 0 && !is_word_char(c) => break,
+                _ => {}
+            }
+            self.bump();
+        }
+        self.src[start..self.pos].to_string()
+    }
+
+    fn string(&mut self, start: Span) -> Result<Token, ParseError> {
+        self.bump();
+        let mut out = String::new();
+        loop {
+            let Some(c) = self.bump() else {
+                return self.error(start, "unterminated string".to_string());
+            };
+            match c {
+                '"' => return Ok(Token::Str(out)),
+                '\\' => match self.bump() {
+                    Some('"') => out.push('"'),
+                    Some('\\') => out.push('\\'),
+                    Some('n') => out.push('\n'),
+                    Some('t') => out.push('\t'),
+                    Some('r') => out.push('\r'),
+                    // empty escape, ends a numeric one: "\1\&2"
+                    Some('&') => {}
+                    Some(d) if d.is_ascii_digit() => {
+                        let mut code = d.to_digit(10).unwrap_or_default();
+                        while let Some(d) = self.peek().and_then(|c| c.to_digit(10)) {
+                            code = code.saturating_mul(10).saturating_add(d);
+                            self.bump();
+                        }
+                        match char::from_u32(code) {
+                            Some(c) => out.push(c),
+                            None => return self.error(start, format!("invalid character code \\{}", code)),
+                        }
+                    }
+                    // string gap: a backslash, whitespace (usually a newline) and another backslash
+                    Some(w) if w.is_whitespace() => {
+                        while self.peek().is_some_and(|c| c.is_whitespace()) {
+                            self.bump();
+                        }
+                        if self.bump() != Some('\\') {
+                            return self.error(start, "unterminated string gap, expected '\\'".to_string());
+                        }
+                    }
+                    Some(other) => return self.error(start, format!("unknown escape \\{}", other)),
+                    None => return self.error(start, "unterminated string".to_string()),
+                },
+                c => out.push(c),
+            }
+        }
+    }
+}
+
+// `12`, `-3`, `1.5`; None for anything else that starts like a number
+fn number(text: &str) -> Option<Token> {
+    let digits = text.strip_prefix(['-', '+']).unwrap_or(text);
+    match digits.split_once('.') {
+        None => PactInteger::parse(text).ok().map(Token::Int),
+        Some((int, frac)) if !int.is_empty() && !frac.is_empty() && int.bytes().chain(frac.bytes()).all(|b| b.is_ascii_digit()) => {
+            PactDecimal::parse(text).ok().map(Token::Dec)
+        }
+        _ => None,
+    }
+}
+
+struct Parser {
+    tokens: Vec<(Token, Span)>,
+    pos: usize,
+    // forms being parsed around the current one
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos].0
+    }
+
+    fn at_end(&self) -> bool {
+        *self.peek() == Token::Eof
+    }
+
+    fn next(&mut self) -> (Token, Span) {
+        let item = self.tokens[self.pos].clone();
+        if item.0 != Token::Eof {
+            self.pos += 1;
+        }
+        item
+    }
+
+    fn unexpected<T>(&self, token: &Token, span: Span, expected: &str) -> Result<T, ParseError> {
+        Err(ParseError { message: format!("expected {}, found {}", expected, token.describe()), span })
+    }
+
+    fn expect(&mut self, want: Token, expected: &str) -> Result<Span, ParseError> {
+        let (token, span) = self.next();
+        if token == want {
+            Ok(span)
+        } else {
+            self.unexpected(&token, span, expected)
+        }
+    }
+
+    // Name of a binding, parameter or definition: `x` or `x:type`
+    fn name(&mut self, expected: &str) -> Result<(String, Option<String>), ParseError> {
+        match self.next() {
+            (Token::Word(w), span) if !w.contains('.') => {
+                check_ident(&w).map_err(|message| ParseError { message, span })?;
+                Ok((w, None))
+            }
+            (Token::Typed(n, t), span) if !n.contains('.') => {
+                typed_name(&n, &t).map_err(|message| ParseError { message, span })?;
+                Ok((n, Some(t)))
+            }
+            (token, span) => self.unexpected(&token, span, expected),
+        }
+    }
+
+    fn form(&mut self) -> Result<Spanned, ParseError> {
+        if self.depth >= MAX_DEPTH {
+            let span = self.tokens[self.pos].1;
+            return Err(ParseError { message: format!("nested more than {} levels deep", MAX_DEPTH), span });
+        }
+        self.depth += 1;
+        let form = self.nested_form();
+        self.depth -= 1;
+        form
+    }
+
+    fn nested_form(&mut self) -> Result<Spanned, ParseError> {
+        let (token, span) = self.next();
+        let leaf = |expr| Ok(Spanned { expr, span, children: vec![] });
+        match token {
+            Token::LParen => self.parenthesized(span),
+            Token::LBracket => {
+                let mut children = Vec::new();
+                loop {
+                    match self.peek() {
+                        Token::RBracket => break,
+                        Token::Comma => { self.next(); }
+                        Token::Eof => return unclosed('[', span),
+                        _ => children.push(self.form()?),
+                    }
+                }
+                let end = self.next().1;
+                Ok(node(PactExpr::List(exprs(&children)), span.to(end), children))
+            }
+            Token::LBrace => self.object(span),
+            Token::Str(s) => leaf(PactExpr::String(s)),
+            Token::Int(i) => leaf(PactExpr::Integer(i)),
+            Token::Dec(d) => leaf(PactExpr::Decimal(d)),
+            Token::Word(w) => leaf(word_expr(&w).map_err(|message| ParseError { message, span })?),
+            Token::Typed(name, ty) => {
+                typed_name(&name, &ty).map_err(|message| ParseError { message, span })?;
+                leaf(PactExpr::Typed { name, ty })
+            }
+            Token::Meta(name) => self.meta(name, span),
+            Token::RParen | Token::RBracket | Token::RBrace => Err(ParseError { message: format!("unmatched {}", token.describe()), span }),
+            token => self.unexpected(&token, span, "an expression"),
+        }
+    }
+
+    fn parenthesized(&mut self, open: Span) -> Result<Spanned, ParseError> {
+        let keyword = match self.peek() {
+            Token::Word(w) => Some(w.clone()),
+            Token::RParen => {
+                let span = open.to(self.next().1);
+                return Err(ParseError { message: "empty expression ()".to_string(), span });
+            }
+            Token::Eof => return unclosed('(', open),
+            _ => None,
+        };
+        match keyword.as_deref() {
+            Some("let") | Some("let*") => {
+                self.next();
+                self.let_form(open, keyword.as_deref() == Some("let*"))
+            }
+            Some(kw) if DefKind::from_keyword(kw).is_some() => {
+                self.next();
+                self.def(open, DefKind::from_keyword(kw).unwrap_or(DefKind::Defun))
+            }
+            _ => {
+                let mut children = vec![self.form()?];
+                let end = self.rest(open, &mut children)?;
+                let args = exprs(&children[1..]);
+                Ok(node(PactExpr::App(Box::new(children[0].expr.clone()), args), open.to(end), children))
+            }
+        }
+    }
+
+    // Forms up to and including the ')' closing `open`
+    fn rest(&mut self, open: Span, children: &mut Vec<Spanned>) -> Result<Span, ParseError> {
+        while *self.peek() != Token::RParen {
+            if self.at_end() {
+                return unclosed('(', open);
+            }
+            children.push(self.form()?);
+        }
+        Ok(self.next().1)
+    }
+
+    fn let_form(&mut self, open: Span, star: bool) -> Result<Spanned, ParseError> {
+        self.expect(Token::LParen, "'(' before let bindings")?;
+        let mut bindings = Vec::new();
+        let mut children = Vec::new();
+        while *self.peek() != Token::RParen {
+            self.expect(Token::LParen, "'(' of a let binding")?;
+            let name = match self.name("a binding name")? {
+                (n, Some(t)) => format!("{}:{}", n, t),
+                (n, None) => n,
+            };
+            let value = self.form()?;
+            self.expect(Token::RParen, "')' after a let binding")?;
+            bindings.push((name, value.expr.clone()));
+            children.push(value);
+        }
+        self.next();
+        let end = self.rest(open, &mut children)?;
+        let body = exprs(&children[bindings.len()..]);
+        let expr = if star { PactExpr::LetStar(bindings, body) } else { PactExpr::Let(bindings, body) };
+        Ok(node(expr, open.to(end), children))
+    }
+
+    fn def(&mut self, open: Span, kind: DefKind) -> Result<Spanned, ParseError> {
+        let (name, ty) = self.name(&format!("a {} name", kind.keyword()))?;
+        let params = match kind {
+            DefKind::Defschema | DefKind::Deftable => None,
+            _ => {
+                self.expect(Token::LParen, "'(' before parameters")?;
+                let mut params = Vec::new();
+                while *self.peek() != Token::RParen {
+                    params.push(self.name("a parameter name")?);
+                }
+                self.next();
+                Some(params)
+            }
+        };
+        let mut children = Vec::new();
+        let end = self.rest(open, &mut children)?;
+        let def = PactDef { kind, name, ty, params, body: exprs(&children) };
+        Ok(node(PactExpr::Def(Box::new(def)), open.to(end), children))
+    }
+
+    fn object(&mut self, open: Span) -> Result<Spanned, ParseError> {
+        let mut fields = Vec::new();
+        let mut children = Vec::new();
+        let mut binding = None;
+        loop {
+            let key = match self.next() {
+                (Token::RBrace, end) => {
+                    let expr = if binding == Some(true) { PactExpr::Bindings(fields) } else { PactExpr::Object(fields) };
+                    return Ok(node(expr, open.to(end), children));
+                }
+                (Token::Comma, _) if !fields.is_empty() => continue,
+                (Token::Eof, _) => return unclosed('{', open),
+                (Token::Str(key), _) => key,
+                (token, span) => return self.unexpected(&token, span, "a field name string or '}'"),
+            };
+            let is_binding = match self.next() {
+                (Token::Colon, _) => false,
+                (Token::Bind, _) => true,
+                (token, span) => return self.unexpected(&token, span, "':' or ':='"),
+            };
+            if binding.is_some_and(|b| b != is_binding) {
+                let span = self.tokens[self.pos - 1].1;
+                return Err(ParseError { message: "object mixes ':' fields and ':=' bindings".to_string(), span });
+            }
+            binding = Some(is_binding);
+            let value = self.form()?;
+            fields.push((key, value.expr.clone()));
+            children.push(value);
+        }
+    }
+
+    // @doc "..", @model [..], @event, @managed or @managed param manager
+    fn meta(&mut self, name: String, span: Span) -> Result<Spanned, ParseError> {
+        let mut children = Vec::new();
+        match name.as_str() {
+            "doc" | "model" => children.push(self.form()?),
+            "event" => {}
+            "managed" => {
+                while children.len() < 2 && matches!(self.peek(), Token::Word(_)) {
+                    children.push(self.form()?);
+                }
+            }
+            _ => return Err(ParseError { message: format!("unknown metadata @{}", name), span }),
+        }
+        let full = children.last().map(|c| span.to(c.span)).unwrap_or(span);
+        Ok(node(PactExpr::Meta(name, exprs(&children)), full, children))
+    }
+}
+
+fn unclosed<T>(bracket: char, open: Span) -> Result<T, ParseError> {
+    Err(ParseError { message: format!("unclosed '{}'", bracket), span: open })
+}
+
+fn node(expr: PactExpr, span: Span, children: Vec<Spanned>) -> Spanned {
+    Spanned { expr, span, children }
+}
+
+fn exprs(nodes: &[Spanned]) -> Vec<PactExpr> {
+    nodes.iter().map(|n| n.expr.clone()).collect()
+}
+
+// Names are held to the identifiers `PactExpr` renders, so parsed code always renders
+fn typed_name(name: &str, ty: &str) -> Result<(), String> {
+    check_ident(name).and_then(|_| check_type(ty))
+}
+
+fn word_expr(word: &str) -> Result<PactExpr, String> {
+    match word {
+        "true" => Ok(PactExpr::Bool(true)),
+        "false" => Ok(PactExpr::Bool(false)),
+        w if w.contains('.') => {
+            let parts: Vec<&str> = w.split('.').collect();
+            if parts.iter().any(|p| p.is_empty()) {
+                return Err(format!("invalid qualified name {:?}", w));
+            }
+            parts.iter().try_for_each(|p| check_ident(p))?;
+            match parts.as_slice() {
+                [module, name] => Ok(PactExpr::Qualified { namespace: None, module: module.to_string(), name: name.to_string() }),
+                [ns, module, name] => Ok(PactExpr::Qualified { namespace: Some(ns.to_string()), module: module.to_string(), name: name.to_string() }),
+                _ => Err(format!("too many parts in qualified name {:?}", w)),
+            }
+        }
+        w => {
+            check_ident(w.strip_prefix('\'').unwrap_or(w))?;
+            Ok(PactExpr::Symbol(w.to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLES: &[&str] = &[
+        r#"(coin.transfer "alice" "bob" 1.5)"#,
+        r#"(free.m.f -3 +4 0.000001 true false)"#,
+        r#"(+ 100000000000000000000000 -100000000000000000000000)"#,
+        r#"["a\"b\\c\n\t" 1 {"k": [1 2], "l": {}}]"#,
+        r#"(with-read accounts id {"balance" := bal, "guard" := g} (enforce-guard g))"#,
+        r#"(let ((a 1) (b:decimal 2.0)) (let* ((c (+ a 1))) c))"#,
+        r#"(defun transfer:string (from:string to:string amount:decimal) @doc "move" (enforce (> amount 0.0) "positive"))"#,
+        r#"(defcap TRANSFER (sender:string receiver:string amount:decimal) @managed amount TRANSFER-mgr @event true)"#,
+        r#"(defschema account @doc "row" balance:decimal guard:guard)"#,
+        r#"(deftable accounts:{account})"#,
+        r#"(defpact p () (step (+ 1 2)) (step-with-rollback 1 2))"#,
+        r#"(module m GOV @model [(property true)] (defconst X:[string] ["a"]) (implements fungible-v2))"#,
+        r#"(enforce-keyset 'admin-ks)"#,
+    ];
+
+    fn reparse(src: &str) -> Vec<PactExpr> {
+        parse(src).unwrap_or_else(|e| panic!("{}: {}", src, e)).into_iter().map(|f| f.expr).collect()
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for src in SAMPLES {
+            let exprs = reparse(src);
+            let rendered: Vec<String> = exprs.iter().map(|e| e.to_string()).collect();
+            assert_eq!(reparse(&rendered.join("\n")), exprs, "{}", src);
+        }
+    }
+
+    #[test]
+    fn names_that_do_not_render_are_parse_errors() {
+        for src in ["(f a')", "(coin.'x 1)", "(f x:'a)", "(let ((a':string 1)) a)", "(defun f (x:{a b}) x)", "''a"] {
+            assert!(parse(src).is_err(), "{}", src);
+        }
+        for src in SAMPLES {
+            for form in reparse(src) {
+                assert_eq!(form.render(), Ok(form.to_string()), "{}", src);
+            }
+        }
+    }
+
+    #[test]
+    fn strings_with_control_characters_round_trip() {
+        for s in ["\u{1}2", "\u{0}", "a\u{7f}9b", "\u{1b}[0m", "\r\n\t\"\\"] {
+            let expr = PactExpr::string(s);
+            assert_eq!(parse_expr(&expr.to_string()).unwrap().expr, expr, "{:?}", s);
+        }
+        assert_eq!(parse_expr(r#""\1\&2""#).unwrap().expr, PactExpr::string("\u{1}2"));
+        assert_eq!(parse_expr(r#""\12""#).unwrap().expr, PactExpr::string("\u{c}"));
+    }
+
+    #[test]
+    fn big_integers_are_exact() {
+        let src = "(+ 100000000000000000000000 1)";
+        let PactExpr::App(_, args) = parse_expr(src).unwrap().expr else { panic!("not an application") };
+        assert_eq!(args[0], PactExpr::Integer(PactInteger::parse("100000000000000000000000").unwrap()));
+        assert_eq!(args[1].to_string(), "1");
+        assert_eq!(parse_expr(src).unwrap().expr.to_string(), src);
+        assert_eq!(parse_expr("-007").unwrap().expr, PactExpr::integer(-7));
+        assert!(parse("1x").is_err());
+    }
+
+    #[test]
+    fn spans_cover_the_source_text() {
+        let src = "; transfer\n(coin.transfer \"alice\"\n  \"bob\" 1.50)";
+        let form = parse_expr(src).unwrap();
+        assert_eq!(form.span.text(src), "(coin.transfer \"alice\"\n  \"bob\" 1.50)");
+        assert_eq!((form.span.line, form.span.column, form.span.end_line, form.span.end_column), (2, 1, 3, 14));
+        let texts: Vec<&str> = form.children.iter().map(|c| c.span.text(src)).collect();
+        assert_eq!(texts, ["coin.transfer", "\"alice\"", "\"bob\"", "1.50"]);
+        let amount = form.children[3].span;
+        assert_eq!((amount.line, amount.column, amount.end_column), (3, 9, 13));
+        assert_eq!(form.children.len(), form.expr.children().len());
+    }
+
+    #[test]
+    fn spans_of_nested_forms_and_errors() {
+        let src = "(let ((a {\"b\": [1 2]})) a)";
+        let form = parse_expr(src).unwrap();
+        let object = &form.children[0];
+        assert_eq!(object.span.text(src), "{\"b\": [1 2]}");
+        assert_eq!(object.children[0].span.text(src), "[1 2]");
+        assert_eq!(object.children[0].children[1].span.text(src), "2");
+        assert_eq!(form.children[1].span.text(src), "a");
+
+        let err = parse("(f\n  \"open)").unwrap_err();
+        assert_eq!((err.span.line, err.span.column), (2, 3));
+        let err = parse("(f ]").unwrap_err();
+        assert_eq!(err.span.text("(f ]"), "]");
+    }
+
+    #[test]
+    fn nesting_is_limited() {
+        let deep = "(".repeat(20000);
+        let err = parse(&deep).unwrap_err();
+        assert!(err.message.contains("levels deep"), "{}", err);
+        let deep_list = format!("{}1{}", "[".repeat(MAX_DEPTH + 1), "]".repeat(MAX_DEPTH + 1));
+        assert!(parse(&deep_list).is_err());
+        let ok = format!("{}1{}", "[".repeat(MAX_DEPTH - 1), "]".repeat(MAX_DEPTH - 1));
+        let form = parse_expr(&ok).unwrap();
+        assert_eq!(parse_expr(&form.expr.to_string()).unwrap().expr, form.expr);
+    }
+}
@@ -0,0 +1,39 @@
+use rust_pact::chrono::{DateTime, Utc};
+use rust_pact::codegen::generate_bindings_from_source;
+use rust_pact::types::Payload;
+use rust_pact::PactDecimal;
+use serde_json::json;
+
+include!("fixtures/token_bindings.rs");
+
+const SOURCE: &str = include_str!("fixtures/token.pact");
+
+#[test]
+fn bindings_match_the_checked_in_output() {
+    assert_eq!(generate_bindings_from_source("free.token", SOURCE).unwrap(), include_str!("fixtures/token_bindings.rs"));
+}
+
+#[test]
+fn generated_calls_render_the_module_call() {
+    let amount = PactDecimal::parse("1.50").unwrap();
+    assert_eq!(free_token::transfer_expr("k:a", "k:b\" x", &amount).to_string(), r#"(free.token.transfer "k:a" "k:b\" x" 1.5)"#);
+    let cmd = free_token::transfer("k:a", "k:b", &amount).unwrap()
+        .set_meta("0", "k:a")
+        .set_network_id("testnet04")
+        .create_transaction()
+        .unwrap();
+    assert_eq!(cmd.payload, Payload::exec(r#"(free.token.transfer "k:a" "k:b" 1.5)"#, json!({})));
+
+    let at: DateTime<Utc> = "2024-01-02T03:04:05.5Z".parse().unwrap();
+    assert_eq!(free_token::expires_expr(at, 3).to_string(), r#"(free.token.expires (parse-time "%Y-%m-%dT%H:%M:%S.%vZ" "2024-01-02T03:04:05.500000Z") 3)"#);
+    let too_fine: DateTime<Utc> = "2024-01-02T03:04:05.000000001Z".parse().unwrap();
+    assert!(free_token::expires(too_fine, 3).is_err());
+}
+
+#[test]
+fn capability_helpers_use_pact_json() {
+    let cap = free_token::caps::transfer("k:a", "k:b", PactDecimal::from(2));
+    assert_eq!(cap.name, "free.token.TRANSFER");
+    assert_eq!(cap.args, vec![json!("k:a"), json!("k:b"), json!({"decimal": "2.0"})]);
+    assert!(free_token::caps::gov().args.is_empty());
+}
@@ -0,0 +1,55 @@
+// Generated by rust_pact::codegen from the interface of free.token. Do not edit.
+pub mod free_token {
+    #![allow(dead_code, unused_imports, non_snake_case, clippy::too_many_arguments)]
+    use rust_pact::chrono::{DateTime, Utc};
+    use rust_pact::decimal::PactDecimal;
+    use rust_pact::env::time_json;
+    use rust_pact::expr::{PactExpr, ToPact};
+    use rust_pact::serde_json::{json, Value};
+    use rust_pact::types::Capability;
+    use rust_pact::TransactionBuilder;
+
+    pub const MODULE: &str = "free.token";
+
+    fn call(name: &str, args: Vec<PactExpr>) -> PactExpr {
+        PactExpr::call(&format!("{}.{}", MODULE, name), args).expect("names come from the module's code")
+    }
+
+    /// Move amount from sender to receiver
+    ///
+    /// `(defun transfer:string (sender:string receiver:string amount:decimal))`
+    pub fn transfer_expr(sender: &str, receiver: &str, amount: impl Into<PactDecimal>) -> PactExpr {
+        call("transfer", vec![PactExpr::string(sender), PactExpr::string(receiver), PactExpr::decimal(amount)])
+    }
+
+    /// Move amount from sender to receiver
+    ///
+    /// `(defun transfer:string (sender:string receiver:string amount:decimal))`
+    pub fn transfer(sender: &str, receiver: &str, amount: impl Into<PactDecimal>) -> Result<TransactionBuilder, String> {
+        Ok(TransactionBuilder::new().execution(&transfer_expr(sender, receiver, amount).render()?))
+    }
+
+    /// `(defun expires (at:time count:integer))`
+    pub fn expires_expr(at: DateTime<Utc>, count: i64) -> PactExpr {
+        call("expires", vec![PactExpr::time(at), PactExpr::integer(count)])
+    }
+
+    /// `(defun expires (at:time count:integer))`
+    pub fn expires(at: DateTime<Utc>, count: i64) -> Result<TransactionBuilder, String> {
+        Ok(TransactionBuilder::new().execution(&expires_expr(at, count).render()?))
+    }
+
+    pub mod caps {
+        use super::*;
+
+        /// `(defcap GOV ())`
+        pub fn gov() -> Capability {
+            Capability::new(&format!("{}.{}", MODULE, "GOV"), vec![])
+        }
+
+        /// `(defcap TRANSFER:bool (sender:string receiver:string amount:decimal))`
+        pub fn transfer(sender: &str, receiver: &str, amount: impl Into<PactDecimal>) -> Capability {
+            Capability::new(&format!("{}.{}", MODULE, "TRANSFER"), vec![json!(sender), json!(receiver), amount.into().to_json()])
+        }
+    }
+}
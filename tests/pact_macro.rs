@@ -0,0 +1,32 @@
+use rust_pact::{pact, PactDecimal, PactExpr, PactInteger};
+
+#[test]
+fn strings_are_escaped() {
+    let sender = "k:a\") (coin.transfer \"x";
+    assert_eq!(pact!("(coin.details {sender})"), r#"(coin.details "k:a\") (coin.transfer \"x")"#);
+    assert_eq!(pact!("(f {})", "\u{1}2"), r#"(f "\1\&2")"#);
+}
+
+#[test]
+fn numbers_lists_and_expressions_are_interpolated() {
+    let amount = PactDecimal::parse("1.50").unwrap();
+    let big = PactInteger::parse("100000000000000000000000").unwrap();
+    let ks = PactExpr::read_keyset("ks");
+    assert_eq!(
+        pact!("(free.m.f {amount} {big} {} {ks} {names})", 7, names = vec!["a", "b"]),
+        r#"(free.m.f 1.5 100000000000000000000000 7 (read-keyset "ks") ["a" "b"])"#
+    );
+}
+
+#[test]
+fn positional_named_and_braces() {
+    let key = "k";
+    assert_eq!(pact!("(put {0} {{\"v\": {v}, \"w\": {0}}})", key, v = true), r#"(put "k" {"v": true, "w": "k"})"#);
+    assert_eq!(pact!("(coin.details \"plain\") ; comment {{}}"), "(coin.details \"plain\") ; comment {}");
+}
+
+#[test]
+fn integers_of_every_width_are_exact() {
+    let (big, count, low) = (u64::MAX, 3usize, i128::MIN);
+    assert_eq!(pact!("(f {big} {count} {low})"), "(f 18446744073709551615 3 -170141183460469231731687303715884105728)");
+}